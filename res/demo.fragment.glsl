@@ -0,0 +1,11 @@
+uniform vec4 base_color;
+
+in vec3 v_position;
+in vec3 v_normal;
+out vec4 f_color;
+
+#include "lighting.glsl"
+
+void main() {
+    f_color = vec4(shade(v_position, v_normal, base_color.rgb), base_color.a);
+}
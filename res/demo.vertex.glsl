@@ -0,0 +1,15 @@
+uniform mat4 persp_matrix;
+uniform mat4 view_matrix;
+uniform mat4 model_matrix;
+uniform mat3 normal_matrix;
+
+in vec3 position;
+in vec3 normal;
+out vec3 v_position;
+out vec3 v_normal;
+
+void main() {
+    v_position = (model_matrix * vec4(position, 1.0)).xyz;
+    v_normal = normal_matrix * normal;
+    gl_Position = persp_matrix * view_matrix * vec4(v_position, 1.0);
+}
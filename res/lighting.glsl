@@ -0,0 +1,51 @@
+// Blinn-Phong lighting by the lights of the scene; see `scene::Renderer` for the uniforms.
+
+uniform vec3 camera_position;
+
+uniform vec3 ambient_light;
+uniform int directional_light_count;
+uniform vec3 directional_light_directions[MAX_DIRECTIONAL_LIGHTS];
+uniform vec3 directional_light_radiances[MAX_DIRECTIONAL_LIGHTS];
+uniform int point_light_count;
+uniform vec3 point_light_positions[MAX_POINT_LIGHTS];
+uniform vec3 point_light_radiances[MAX_POINT_LIGHTS];
+uniform float point_light_ranges[MAX_POINT_LIGHTS];
+
+uniform vec3 specular;
+uniform float shininess;
+uniform vec3 emissive;
+
+// diffuse and specular reflection of a single light arriving from `to_light`
+vec3 reflected(vec3 normal, vec3 to_camera, vec3 to_light, vec3 radiance, vec3 albedo) {
+    float lambert = max(dot(normal, to_light), 0.0);
+    if (lambert <= 0.0) {
+        return vec3(0.0);
+    }
+    vec3 halfway = normalize(to_light + to_camera);
+    float highlight = pow(max(dot(normal, halfway), 0.0), shininess);
+    return (albedo * lambert + specular * highlight) * radiance;
+}
+
+// inverse square falloff smoothly reaching 0 at `range`
+float attenuation(float light_distance, float range) {
+    float ratio = light_distance / range;
+    float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / max(light_distance * light_distance, 0.0001);
+}
+
+// color of a surface with the given albedo at `position` in world space
+vec3 shade(vec3 position, vec3 normal, vec3 albedo) {
+    normal = normalize(normal);
+    vec3 to_camera = normalize(camera_position - position);
+    vec3 color = emissive + ambient_light * albedo;
+    for (int i = 0; i < directional_light_count; ++i) {
+        color += reflected(normal, to_camera, directional_light_directions[i], directional_light_radiances[i], albedo);
+    }
+    for (int i = 0; i < point_light_count; ++i) {
+        vec3 to_light = point_light_positions[i] - position;
+        float light_distance = length(to_light);
+        vec3 radiance = point_light_radiances[i] * attenuation(light_distance, point_light_ranges[i]);
+        color += reflected(normal, to_camera, to_light / max(light_distance, 0.0001), radiance, albedo);
+    }
+    return color;
+}
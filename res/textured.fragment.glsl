@@ -0,0 +1,15 @@
+uniform vec4 base_color;
+uniform sampler2D diffuse_texture;
+
+in vec3 v_position;
+in vec3 v_normal;
+in vec2 v_tex_coords;
+out vec4 f_color;
+
+#include "lighting.glsl"
+
+void main() {
+    vec4 diffuse = base_color * texture(diffuse_texture, v_tex_coords);
+    vec3 color = shade(v_position, v_normal, diffuse.rgb);
+    f_color = vec4(color, diffuse.a);
+}
@@ -0,0 +1,188 @@
+use std::time::{Duration, Instant};
+
+use glium::{Display, Surface};
+use glium::backend::glutin::DisplayCreationError;
+use glium::glutin::event::{ DeviceEvent, Event, WindowEvent };
+use glium::glutin::event_loop::{ EventLoop, ControlFlow };
+use log::trace;
+
+use crate::display;
+use crate::settings::Settings;
+use crate::timing::FixedStep;
+
+/// Tells the main loop whether it shall keep on running.
+pub enum Action {
+    Stop,
+    Continue,
+}
+
+/// Callbacks through which the engine drives a game.
+pub trait App {
+
+    /// Called for every window event received since the previous frame; return `Action::Stop` to quit.
+    fn handle_event(&mut self, _event: &WindowEvent<'_>) -> Action {
+        Action::Continue
+    }
+
+    /// Called for every raw device event (e.g. relative mouse motion) received since the previous frame.
+    fn handle_device_event(&mut self, _event: &DeviceEvent) -> Action {
+        Action::Continue
+    }
+
+    /// Called once per simulation tick to advance the game state by `time` seconds.
+    ///
+    /// `time` is the same for every call, regardless of how fast frames are being rendered.
+    fn update(&mut self, time: f32);
+
+    /// Called once per rendered frame to draw the current state into `target`.
+    ///
+    /// `alpha` is the time elapsed since the most recent tick as fraction of a tick; rendering should interpolate
+    /// between the previous and the current simulation state accordingly.
+    ///
+    /// `target` is the window's frame while running the main loop, but may as well be an offscreen frame buffer, e.g.
+    /// when capturing a frame with [`crate::capture::Offscreen`].
+    fn draw<S: Surface>(&mut self, target: &mut S, alpha: f32);
+
+    /// Called once right before the main loop terminates; the place to persist state.
+    fn shutdown(&mut self) {}
+
+}
+
+/// Owns the window and the OpenGL context and runs the main loop.
+pub struct Engine {
+    event_loop: EventLoop<()>,
+    display: Display,
+    time_step: FixedStep,
+}
+
+impl Engine {
+
+    /// Opens a window as described by `settings`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the window or its OpenGL context cannot be created.
+    pub fn new(settings: &Settings) -> Result<Self, DisplayCreationError> {
+        let event_loop = EventLoop::new();
+        let display = display::create(&event_loop, settings)?;
+        display::dump_details(&display);
+
+        Ok(Self { event_loop, display, time_step: FixedStep::default() })
+    }
+
+    /// Sets the number of simulation ticks per second and the longest frame time that will be caught up with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick_rate` is zero.
+    pub fn set_time_step(&mut self, tick_rate: u32, max_frame_time: Duration) {
+        self.time_step = FixedStep::new(tick_rate, max_frame_time);
+    }
+
+    /// Number of simulation ticks per second.
+    #[must_use]
+    pub fn tick_rate(&self) -> u32 {
+        self.time_step.tick_rate()
+    }
+
+    /// The display to create buffers, textures and shader programs with.
+    #[must_use]
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    /// Hands the control over to the main loop which will feed `app` until it asks to stop or the window gets closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a frame could not be swapped onto the screen.
+    pub fn run<A>(self, mut app: A) -> !
+    where
+        A: 'static + App
+    {
+        let Self { event_loop, display, mut time_step } = self;
+        let tick_time = time_step.tick().as_secs_f32();
+
+        start_loop(event_loop, move |events| {
+            let mut action = Action::Continue;
+
+            // polling and handling the events received by the window
+            for event in events {
+                match event {
+                    Event::WindowEvent { event, .. } => {
+                        if let WindowEvent::CloseRequested = event {
+                            action = Action::Stop;
+                        }
+                        if let Action::Stop = app.handle_event(event) {
+                            action = Action::Stop;
+                        }
+                    },
+                    Event::DeviceEvent { event, .. } => {
+                        if let Action::Stop = app.handle_device_event(event) {
+                            action = Action::Stop;
+                        }
+                    },
+                    _ => {},
+                }
+            }
+
+            // catching up with the time that passed since the previous frame
+            let steps = time_step.advance(Instant::now());
+            trace!("simulating {} tick(s), alpha {:.3}", steps.ticks, steps.alpha);
+            for _ in 0..steps.ticks {
+                app.update(tick_time);
+            }
+
+            // drawing a frame
+            let mut target = display.draw();
+            app.draw(&mut target, steps.alpha);
+            target.finish().unwrap();
+
+            if let Action::Stop = action {
+                app.shutdown();
+            }
+
+            action
+        })
+    }
+
+}
+
+/// Runs `event_loop` and calls `callback` with the events collected since the previous frame.
+///
+/// The callback is invoked as soon as all pending events have been received; the frame rate is therefore only
+/// limited by the time it takes to draw and swap a frame (i.e. vsync).
+pub fn start_loop<F>(event_loop: EventLoop<()>, mut callback: F)->!
+where
+    F: 'static + FnMut(&Vec<Event<'_, ()>>) -> Action
+{
+    let mut events_buffer = Vec::new();
+    event_loop.run(move |event, _, control_flow| {
+        let run_callback = match event.to_static() {
+            Some(Event::MainEventsCleared) => true,
+            Some(Event::NewEvents(_)) | None => {
+                // Ignore this event.
+                false
+            },
+            Some(event) => {
+                events_buffer.push(event);
+                false
+            }
+        };
+
+        let action = if run_callback {
+            let action = callback(&events_buffer);
+            events_buffer.clear();
+            action
+        } else {
+            Action::Continue
+        };
+
+        match action {
+            Action::Continue => {
+                *control_flow = ControlFlow::Poll;
+            },
+            Action::Stop => *control_flow = ControlFlow::Exit
+        }
+    })
+}
@@ -0,0 +1,429 @@
+//! Loading of assets by logical path with caching.
+//!
+//! [`Assets`] looks files up in a [`Vfs`] and hands out [`Handle`]s. As long as any handle to an asset is alive,
+//! loading it again returns the same handle instead of reading the file once more. Assets whose files have changed
+//! can be reloaded in place with [`Assets::reload_changed`], e.g. while developing shaders.
+
+use std::any::{Any, TypeId};
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::time::SystemTime;
+use std::{error, fmt, fs, io};
+
+use log::{debug, info, warn};
+
+use crate::model::{self, Mesh, Model, WavefrontModel};
+use crate::texture::{ColorSpace, TextureData};
+
+pub use vfs::Vfs;
+pub(crate) use vfs::relative_path;
+
+mod vfs;
+
+/// Errors of loaders, which differ for each kind of asset.
+pub type DecodeError = Box<dyn error::Error + Send + Sync>;
+
+/// Everything that went wrong while loading an asset.
+#[derive(Debug)]
+pub enum AssetError {
+    /// The logical path is absolute, empty or contains `.` or `..` components
+    InvalidPath(String),
+    /// The file doesn't exist in any of the mounts
+    NotFound { path: String, search_path: Vec<String> },
+    /// The file exists but cannot be read
+    Io { path: String, error: io::Error },
+    /// A ZIP archive cannot be read
+    Archive { path: PathBuf, error: zip::result::ZipError },
+    /// The file has been read but its content is invalid
+    Decode { path: String, error: DecodeError },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath(path) => write!(f, "invalid asset path `{path}`"),
+            Self::NotFound { path, search_path } => {
+                write!(f, "asset `{path}` not found in any of {}", search_path.join(", "))
+            },
+            Self::Io { path, error } => write!(f, "could not read asset `{path}`: {error}"),
+            Self::Archive { path, error } => write!(f, "could not read archive {}: {error}", path.display()),
+            Self::Decode { path, error } => write!(f, "could not load asset `{path}`: {error}"),
+        }
+    }
+}
+
+impl error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            Self::Archive { error, .. } => Some(error),
+            Self::Decode { error, .. } => Some(error.as_ref()),
+            Self::InvalidPath(_) | Self::NotFound { .. } => None,
+        }
+    }
+}
+
+/// Something that can be loaded from a file.
+pub trait Asset: Sized + 'static {
+    /// Options that influence loading; the same file loaded with different parameters results in different assets
+    type Params: Clone + Eq + Hash + Default + 'static;
+
+    /// Decodes the content of the file at the logical `path`.
+    ///
+    /// Loaders may read files referenced by the asset from `vfs`, e.g. with [`Vfs::read_relative`], so they are found
+    /// in the same places as the asset itself, including archives.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `data` is not valid for this kind of asset or a referenced file cannot be read.
+    fn decode(data: &[u8], path: &str, vfs: &Vfs, params: &Self::Params) -> Result<Self, DecodeError>;
+}
+
+struct Entry<T> {
+    path: String,
+    value: RefCell<T>,
+    version: Cell<u32>,
+}
+
+/// A reference counted handle to a loaded asset.
+pub struct Handle<T> {
+    entry: Rc<Entry<T>>,
+}
+
+impl<T> Handle<T> {
+
+    /// Logical path the asset has been loaded from.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.entry.path
+    }
+
+    /// Borrows the asset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the asset is being replaced at the same time, which cannot happen outside of [`Assets`].
+    #[must_use]
+    pub fn get(&self) -> Ref<'_, T> {
+        self.entry.value.borrow()
+    }
+
+    /// Starts at 0 and is incremented each time the asset is reloaded, so users can tell when derived data such as
+    /// GPU buffers needs to be recreated.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.entry.version.get()
+    }
+
+    /// Whether both handles refer to the same asset.
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.entry, &other.entry)
+    }
+
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self { entry: Rc::clone(&self.entry) }
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").field(&self.entry.path).finish()
+    }
+}
+
+/// The assets of a single type that are still referenced.
+type Cache<T> = HashMap<(String, <T as Asset>::Params), Weak<Entry<T>>>;
+
+/// Decodes the given file content into an asset once more; returns `false` if the asset doesn't exist anymore.
+type Reload = Box<dyn Fn(&[u8], &Vfs) -> Result<bool, DecodeError>>;
+
+/// A loaded asset found in a directory, whose file can be checked for changes.
+struct Watched {
+    path: String,
+    file: PathBuf,
+    modified: Option<SystemTime>,
+    reload: Reload,
+}
+
+/// Loads assets from a [`Vfs`], sharing them between all users.
+pub struct Assets {
+    vfs: Vfs,
+    /// a [`Cache`] for each type of asset
+    caches: HashMap<TypeId, Box<dyn Any>>,
+    watched: Vec<Watched>,
+}
+
+impl Assets {
+
+    #[must_use]
+    pub fn new(vfs: Vfs) -> Self {
+        Self { vfs, caches: HashMap::new(), watched: Vec::new() }
+    }
+
+    #[must_use]
+    pub fn vfs(&self) -> &Vfs {
+        &self.vfs
+    }
+
+    /// Loads an asset with default parameters; see [`Assets::load_with`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be found, cannot be read or is invalid.
+    pub fn load<T: Asset>(&mut self, path: &str) -> Result<Handle<T>, AssetError> {
+        self.load_with(path, T::Params::default())
+    }
+
+    /// Returns the asset at the logical `path`, reading it only if it isn't loaded already.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be found, cannot be read or is invalid.
+    pub fn load_with<T: Asset>(&mut self, path: &str, params: T::Params) -> Result<Handle<T>, AssetError> {
+        let key = (path.to_string(), params);
+        if let Some(entry) = self.cache::<T>().get(&key).and_then(Weak::upgrade) {
+            return Ok(Handle { entry });
+        }
+
+        debug!("load asset {path}");
+        let data = self.vfs.read(path)?;
+        let file = self.vfs.resolve(path);
+        let modified = file.as_deref().and_then(modification_time);
+        let value = T::decode(&data, path, &self.vfs, &key.1)
+                .map_err(|error| AssetError::Decode { path: path.to_string(), error })?;
+
+        let entry = Rc::new(Entry { path: path.to_string(), value: RefCell::new(value), version: Cell::new(0) });
+        if let Some(file) = file {
+            let (weak, logical_path, params) = (Rc::downgrade(&entry), path.to_string(), key.1.clone());
+            let reload: Reload = Box::new(move |data, vfs| {
+                let Some(entry) = weak.upgrade() else { return Ok(false) };
+                *entry.value.borrow_mut() = T::decode(data, &logical_path, vfs, &params)?;
+                entry.version.set(entry.version.get().wrapping_add(1));
+                Ok(true)
+            });
+            self.watched.push(Watched { path: path.to_string(), file, modified, reload });
+        }
+
+        let cache = self.cache::<T>();
+        // forget assets that have been dropped in the meantime
+        cache.retain(|_, entry| entry.strong_count() > 0);
+        cache.insert(key, Rc::downgrade(&entry));
+        Ok(Handle { entry })
+    }
+
+    /// Reloads all assets whose files have been modified since they have been loaded and returns how many have been
+    /// reloaded.
+    ///
+    /// Only files found in directories are considered. An asset that fails to load keeps its previous value; the
+    /// error is logged and loading is tried again once the file changes another time. Must not be called while any
+    /// asset is borrowed.
+    pub fn reload_changed(&mut self) -> usize {
+        let mut reloaded = 0;
+        let vfs = &self.vfs;
+        self.watched.retain_mut(|watched| {
+            let modified = modification_time(&watched.file);
+            if modified.is_none() || modified == watched.modified {
+                // editors may remove a file for a moment while saving it
+                return true;
+            }
+            watched.modified = modified;
+
+            let result = fs::read(&watched.file)
+                    .map_err(|error| AssetError::Io { path: watched.path.clone(), error })
+                    .and_then(|data| (watched.reload)(&data, vfs)
+                            .map_err(|error| AssetError::Decode { path: watched.path.clone(), error }));
+            match result {
+                Ok(true) => {
+                    info!("reloaded asset {}", watched.path);
+                    reloaded += 1;
+                    true
+                },
+                Ok(false) => false,
+                Err(err) => {
+                    warn!("Keeping previous version: {err}");
+                    true
+                },
+            }
+        });
+        reloaded
+    }
+
+    /// Returns the cache of the assets of type `T`, creating it on first use.
+    ///
+    /// # Panics
+    ///
+    /// Never panics in practice: the caches are keyed by the type they hold, so the downcast cannot fail.
+    fn cache<T: Asset>(&mut self) -> &mut Cache<T> {
+        let cache = self.caches.entry(TypeId::of::<T>()).or_insert_with(|| Box::new(Cache::<T>::new()));
+        cache.downcast_mut().expect("caches are keyed by their type")
+    }
+
+}
+
+fn modification_time(file: &Path) -> Option<SystemTime> {
+    fs::metadata(file).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Reads the files referenced by the asset at the logical `path` for the model loaders.
+fn referenced<'a>(vfs: &'a Vfs, path: &'a str) -> impl FnMut(&str) -> io::Result<Vec<u8>> + 'a {
+    move |name| vfs.read_relative(path, name).map_err(|err| match err {
+        AssetError::Io { error, .. } => error,
+        err => io::Error::other(err),
+    })
+}
+
+/// Lower case extension of a logical path.
+fn extension(path: &str) -> String {
+    Path::new(path).extension().map(|extension| extension.to_string_lossy().to_ascii_lowercase()).unwrap_or_default()
+}
+
+/// STL and Wavefront files; the parts of the latter are merged.
+impl Asset for Mesh {
+    type Params = ();
+
+    fn decode(data: &[u8], path: &str, vfs: &Vfs, (): &()) -> Result<Self, DecodeError> {
+        match extension(path).as_str() {
+            "stl" => Ok(model::load_stl(&mut Cursor::new(data))?),
+            "obj" => Ok(model::load_wavefront(&mut Cursor::new(data), referenced(vfs, path))?.merged_mesh()),
+            extension => Err(format!("unsupported mesh format `{extension}`").into()),
+        }
+    }
+}
+
+/// glTF and GLB files.
+impl Asset for Model {
+    type Params = ();
+
+    fn decode(data: &[u8], path: &str, vfs: &Vfs, (): &()) -> Result<Self, DecodeError> {
+        Ok(model::load_gltf(&mut Cursor::new(data), referenced(vfs, path))?)
+    }
+}
+
+/// Wavefront files with their materials.
+impl Asset for WavefrontModel {
+    type Params = ();
+
+    fn decode(data: &[u8], path: &str, vfs: &Vfs, (): &()) -> Result<Self, DecodeError> {
+        Ok(model::load_wavefront(&mut Cursor::new(data), referenced(vfs, path))?)
+    }
+}
+
+/// PNG, JPEG and KTX2 files.
+impl Asset for TextureData {
+    type Params = ColorSpace;
+
+    fn decode(data: &[u8], _path: &str, _vfs: &Vfs, color_space: &ColorSpace) -> Result<Self, DecodeError> {
+        Ok(TextureData::decode(data, *color_space)?)
+    }
+}
+
+/// UTF-8 encoded text such as shader sources.
+impl Asset for String {
+    type Params = ();
+
+    fn decode(data: &[u8], _path: &str, _vfs: &Vfs, (): &()) -> Result<Self, DecodeError> {
+        Ok(String::from_utf8(data.to_vec())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn loads_are_deduplicated() {
+        let dir = env::temp_dir().join(format!("reactor-ls-assets-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("shader.glsl"), "void main() {}").unwrap();
+        let mut vfs = Vfs::new();
+        vfs.mount_dir(&dir);
+        let mut assets = Assets::new(vfs);
+
+        let first = assets.load::<String>("shader.glsl").unwrap();
+        let second = assets.load::<String>("shader.glsl").unwrap();
+        assert!(first.ptr_eq(&second));
+        assert_eq!(&*first.get(), "void main() {}");
+
+        // dropped assets are loaded again
+        drop((first, second));
+        fs::write(dir.join("shader.glsl"), "changed").unwrap();
+        assert_eq!(&*assets.load::<String>("shader.glsl").unwrap().get(), "changed");
+
+        assert!(matches!(assets.load::<Mesh>("shader.glsl"), Err(AssetError::Decode { .. })));
+        assert!(matches!(assets.load::<String>("missing.glsl"), Err(AssetError::NotFound { .. })));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn referenced_files_are_read_from_archives() {
+        let dir = env::temp_dir().join(format!("reactor-ls-assets-archive-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mod.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for (name, content) in &[
+            ("models/ship.obj", "mtllib ship.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl hull\nf 1 2 3\n"),
+            ("models/ship.mtl", "newmtl hull\nKd 0 0 1\n"),
+        ] {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            io::Write::write_all(&mut writer, content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        let mut vfs = Vfs::new();
+        vfs.mount_archive(&path).unwrap();
+        let mut assets = Assets::new(vfs);
+
+        let model = assets.load::<WavefrontModel>("models/ship.obj").unwrap();
+        assert_eq!(model.get().materials[0].name, "hull");
+        assert_eq!(model.get().parts[0].material, Some(0));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changed_files_are_reloaded_in_place() {
+        let dir = env::temp_dir().join(format!("reactor-ls-reload-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("shader.glsl");
+        fs::write(&file, "first").unwrap();
+        let mut vfs = Vfs::new();
+        vfs.mount_dir(&dir);
+        let mut assets = Assets::new(vfs);
+
+        let shader = assets.load::<String>("shader.glsl").unwrap();
+        assert_eq!(assets.reload_changed(), 0);
+
+        let set_modified = |seconds| fs::File::options().write(true).open(&file).unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds)).unwrap();
+        fs::write(&file, "second").unwrap();
+        set_modified(1);
+        assert_eq!(assets.reload_changed(), 1);
+        assert_eq!((shader.version(), shader.get().as_str()), (1, "second"));
+
+        // invalid content keeps the previous version
+        fs::write(&file, [0xff, 0xfe]).unwrap();
+        set_modified(2);
+        assert_eq!(assets.reload_changed(), 0);
+        assert_eq!((shader.version(), shader.get().as_str()), (1, "second"));
+
+        drop(shader);
+        fs::write(&file, "third").unwrap();
+        set_modified(3);
+        assert_eq!(assets.reload_changed(), 0);
+        assert!(assets.watched.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
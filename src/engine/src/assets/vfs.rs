@@ -0,0 +1,245 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+use log::{debug, warn};
+use zip::ZipArchive;
+
+use crate::assets::AssetError;
+
+/// A place assets are looked up in.
+enum Mount {
+    Directory(PathBuf),
+    Archive {
+        path: PathBuf,
+        /// names of all files within the archive
+        files: HashSet<String>,
+        archive: RefCell<ZipArchive<File>>,
+    },
+}
+
+impl Mount {
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Directory(dir) => dir.display().to_string(),
+            Self::Archive { path, .. } => path.display().to_string(),
+        }
+    }
+
+}
+
+/// A virtual file system combining directories and ZIP archives into a single tree of logical paths.
+///
+/// Logical paths are relative, use `/` as separator and may not contain `.` or `..` components, e.g.
+/// `models/axis.stl`. Mounts are searched in the order they have been added, so earlier ones override files of later
+/// ones.
+#[derive(Default)]
+pub struct Vfs {
+    mounts: Vec<Mount>,
+}
+
+impl Vfs {
+
+    /// Creates a file system without any mounts.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the search path of `application`, consisting of
+    ///
+    /// 1. the `assets` directory within the user's data directory (e.g. `~/.local/share/<application>/assets`),
+    /// 2. all ZIP archives within the `mods` directory next to it in alphabetical order, and
+    /// 3. `game_dir` containing the assets shipped with the game.
+    ///
+    /// Missing directories are skipped, broken archives are skipped with a warning.
+    #[must_use]
+    pub fn with_search_path(application: &str, game_dir: &Path) -> Self {
+        let mut vfs = Self::new();
+        if let Some(dirs) = ProjectDirs::from("", "", application) {
+            vfs.mount_dir(dirs.data_local_dir().join("assets"));
+
+            let mut archives: Vec<PathBuf> = fs::read_dir(dirs.data_local_dir().join("mods"))
+                    .map(|entries| entries.filter_map(Result::ok).map(|entry| entry.path()).collect())
+                    .unwrap_or_default();
+            archives.retain(|path| path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("zip")));
+            archives.sort();
+            for archive in archives {
+                if let Err(err) = vfs.mount_archive(&archive) {
+                    warn!("Skipping mod {}: {err}", archive.display());
+                }
+            }
+        }
+        vfs.mount_dir(game_dir);
+        vfs
+    }
+
+    /// Adds a directory to the end of the search path; it doesn't have to exist (yet).
+    pub fn mount_dir<P: Into<PathBuf>>(&mut self, dir: P) {
+        let dir = dir.into();
+        debug!("mount directory {}", dir.display());
+        self.mounts.push(Mount::Directory(dir));
+    }
+
+    /// Adds a ZIP archive to the end of the search path.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the archive cannot be opened or its index cannot be read.
+    pub fn mount_archive(&mut self, path: &Path) -> Result<(), AssetError> {
+        debug!("mount archive {}", path.display());
+        let archive_error = |error| AssetError::Archive { path: path.to_path_buf(), error };
+        let file = File::open(path).map_err(|err| archive_error(err.into()))?;
+        let archive = ZipArchive::new(file).map_err(archive_error)?;
+        let files = archive.file_names().map(String::from).collect();
+        self.mounts.push(Mount::Archive { path: path.to_path_buf(), files, archive: RefCell::new(archive) });
+        Ok(())
+    }
+
+    /// Describes all mounts in search order, e.g. for error messages.
+    #[must_use]
+    pub fn search_path(&self) -> Vec<String> {
+        self.mounts.iter().map(Mount::describe).collect()
+    }
+
+    /// Returns the file a logical path refers to if it is found within a mounted directory before any archive
+    /// containing it.
+    #[must_use]
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        if !is_valid(path) {
+            return None;
+        }
+        for mount in &self.mounts {
+            match mount {
+                Mount::Directory(dir) => {
+                    let file = dir.join(path);
+                    if file.is_file() {
+                        return Some(file);
+                    }
+                },
+                Mount::Archive { files, .. } => if files.contains(path) {
+                    return None;
+                },
+            }
+        }
+        None
+    }
+
+    /// Reads the first file found for a logical path.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the path is invalid, the file doesn't exist in any mount or cannot be read.
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, AssetError> {
+        if !is_valid(path) {
+            return Err(AssetError::InvalidPath(path.to_string()));
+        }
+        let io_error = |error| AssetError::Io { path: path.to_string(), error };
+        for mount in &self.mounts {
+            match mount {
+                Mount::Directory(dir) => match fs::read(dir.join(path)) {
+                    Ok(data) => return Ok(data),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                    Err(err) => return Err(io_error(err)),
+                },
+                Mount::Archive { path: archive_path, files, archive } => if files.contains(path) {
+                    let mut archive = archive.borrow_mut();
+                    let mut file = archive.by_name(path)
+                            .map_err(|error| AssetError::Archive { path: archive_path.clone(), error })?;
+                    let mut data = Vec::new();
+                    file.read_to_end(&mut data).map_err(io_error)?;
+                    return Ok(data);
+                },
+            }
+        }
+        Err(AssetError::NotFound { path: path.to_string(), search_path: self.search_path() })
+    }
+
+    /// Reads the file `name` refers to relative to the logical path `file`, e.g. a material library referenced by a
+    /// model.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Vfs::read`].
+    pub fn read_relative(&self, file: &str, name: &str) -> Result<Vec<u8>, AssetError> {
+        self.read(&relative_path(file, name))
+    }
+
+}
+
+/// Resolves `name` relative to the directory of the logical path `file`, removing `.` and `..` components.
+pub(crate) fn relative_path(file: &str, name: &str) -> String {
+    let mut components: Vec<&str> = file.split('/').collect();
+    components.pop();
+    for component in name.split('/') {
+        match component {
+            "" | "." => (),
+            ".." => {
+                components.pop();
+            },
+            component => components.push(component),
+        }
+    }
+    components.join("/")
+}
+
+fn is_valid(path: &str) -> bool {
+    !path.is_empty() && !path.contains('\\') && path.split('/').all(|component| !matches!(component, "" | "." | ".."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn earlier_mounts_override_later_ones() {
+        let root = env::temp_dir().join(format!("reactor-ls-vfs-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (dir, content) in &[("user", "user"), ("game", "game")] {
+            fs::create_dir_all(root.join(dir).join("models")).unwrap();
+            fs::write(root.join(dir).join("models/ship.obj"), content).unwrap();
+        }
+        fs::write(root.join("game/models/track.obj"), "track").unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.mount_dir(root.join("user"));
+        vfs.mount_dir(root.join("game"));
+        assert_eq!(vfs.read("models/ship.obj").unwrap(), b"user");
+        assert_eq!(vfs.read("models/track.obj").unwrap(), b"track");
+        assert_eq!(vfs.resolve("models/track.obj"), Some(root.join("game/models/track.obj")));
+
+        assert!(matches!(vfs.read("models/missing.obj"), Err(AssetError::NotFound { search_path, .. }) if search_path.len() == 2));
+        assert!(matches!(vfs.read("../game/models/track.obj"), Err(AssetError::InvalidPath(_))));
+        assert!(matches!(vfs.read("/etc/passwd"), Err(AssetError::InvalidPath(_))));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn archives_can_be_mounted() {
+        let root = env::temp_dir().join(format!("reactor-ls-vfs-archive-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("mod.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        writer.start_file("models/ship.obj", zip::write::FileOptions::default()).unwrap();
+        io::Write::write_all(&mut writer, b"modded").unwrap();
+        writer.finish().unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.mount_archive(&path).unwrap();
+        vfs.mount_dir(&root);
+        assert_eq!(vfs.read("models/ship.obj").unwrap(), b"modded");
+        // files within archives have no path of their own
+        assert_eq!(vfs.resolve("models/ship.obj"), None);
+        assert_eq!(vfs.resolve("mod.zip"), Some(path));
+        assert_eq!(vfs.read_relative("models/ship.obj", "../models/./ship.obj").unwrap(), b"modded");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use cgmath::{InnerSpace, Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::CameraState;
+
+/// Smoothly follows a moving target without overshooting it, as a critically damped spring does.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spring {
+    value: Vector3<f32>,
+    velocity: Vector3<f32>,
+}
+
+impl Spring {
+
+    /// Creates a spring resting at `value`.
+    #[must_use]
+    pub fn new(value: Vector3<f32>) -> Self {
+        Self { value, velocity: Vector3::new(0.0, 0.0, 0.0) }
+    }
+
+    #[must_use]
+    pub fn value(&self) -> Vector3<f32> {
+        self.value
+    }
+
+    /// Moves the value to `target` immediately and stops it there.
+    pub fn snap(&mut self, target: Vector3<f32>) {
+        *self = Self::new(target);
+    }
+
+    /// Moves the value towards `target` for `time` seconds and returns the new value.
+    ///
+    /// `lag` is roughly the time in seconds the value takes to catch up with a target that has been moved and stays
+    /// put; 0.0 follows the target rigidly.
+    pub fn update(&mut self, target: Vector3<f32>, lag: f32, time: f32) -> Vector3<f32> {
+        if lag <= 0.0 {
+            self.snap(target);
+            return self.value;
+        }
+        // exact for the critically damped case up to an approximation of the exponential decay
+        let omega = 2.0 / lag;
+        let x = omega * time;
+        let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+        let change = self.value - target;
+        let temp = (self.velocity + change * omega) * time;
+        self.velocity = (self.velocity - temp * omega) * decay;
+        self.value = target + (change + temp) * decay;
+        self.value
+    }
+
+}
+
+/// How the [`ChaseCamera`] follows its target.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChaseSettings {
+    /// position of the camera in the coordinate system of the target, which looks along its negative z axis
+    pub offset: [f32; 3],
+    /// distance in front of the target the camera looks at
+    pub look_ahead: f32,
+    /// time in seconds the camera takes to catch up with the target's position; 0.0 follows rigidly
+    pub position_lag: f32,
+    /// time in seconds the camera takes to catch up with the target's orientation; 0.0 follows rigidly
+    pub rotation_lag: f32,
+    /// whether the camera rolls along with the target, e.g. through loopings; otherwise it stays upright
+    pub follow_roll: bool,
+}
+
+impl Default for ChaseSettings {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 1.0, 4.0],
+            look_ahead: 6.0,
+            position_lag: 0.15,
+            rotation_lag: 0.1,
+            follow_roll: true,
+        }
+    }
+}
+
+impl ChaseSettings {
+
+    /// Returns the name of the first key having an invalid value.
+    pub(crate) fn invalid_key(&self) -> Option<&'static str> {
+        let valid_lag = |lag: f32| lag >= 0.0 && lag.is_finite();
+        if !self.offset.iter().all(|value| value.is_finite()) {
+            Some("offset")
+        } else if !(self.look_ahead >= 0.0 && self.look_ahead.is_finite()) {
+            Some("look_ahead")
+        } else if !valid_lag(self.position_lag) {
+            Some("position_lag")
+        } else if !valid_lag(self.rotation_lag) {
+            Some("rotation_lag")
+        } else {
+            None
+        }
+    }
+
+}
+
+/// A third-person camera following a target such as a ship.
+///
+/// The camera is placed at an offset behind the target, looks at a point ahead of it and lags behind its movements
+/// like being attached by springs. In look-behind mode the offset is mirrored to the front of the target and the
+/// camera looks backwards past it.
+#[derive(Clone)]
+pub struct ChaseCamera {
+    settings: ChaseSettings,
+    camera: CameraState,
+    position: Spring,
+    direction: Spring,
+    up: Spring,
+    look_behind: bool,
+    /// whether to jump to the target on the next update instead of moving there smoothly
+    cut: bool,
+}
+
+impl ChaseCamera {
+
+    #[must_use]
+    pub fn new(settings: ChaseSettings, camera: CameraState) -> Self {
+        let origin = Vector3::new(0.0, 0.0, 0.0);
+        Self {
+            settings,
+            camera,
+            position: Spring::new(origin),
+            direction: Spring::new(origin),
+            up: Spring::new(origin),
+            look_behind: false,
+            cut: true,
+        }
+    }
+
+    /// The camera as of the last update.
+    #[must_use]
+    pub fn camera(&self) -> &CameraState {
+        &self.camera
+    }
+
+    /// Gives access to the projection of the camera; its position and orientation are overwritten by the next update.
+    pub fn camera_mut(&mut self) -> &mut CameraState {
+        &mut self.camera
+    }
+
+    #[must_use]
+    pub fn settings(&self) -> &ChaseSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: ChaseSettings) {
+        self.settings = settings;
+    }
+
+    #[must_use]
+    pub fn look_behind(&self) -> bool {
+        self.look_behind
+    }
+
+    /// Switches between looking ahead and looking behind the target; switching cuts to the new view.
+    pub fn set_look_behind(&mut self, look_behind: bool) {
+        if look_behind != self.look_behind {
+            self.look_behind = look_behind;
+            self.cut();
+        }
+    }
+
+    /// Makes the next update jump to the target instead of following it smoothly, e.g. after the target has been
+    /// teleported or this camera has become active.
+    pub fn cut(&mut self) {
+        self.cut = true;
+    }
+
+    /// Follows `target`, the matrix transforming the target's coordinate system into world space, for `time` seconds.
+    pub fn update(&mut self, target: &Matrix4<f32>, time: f32) {
+        let position = target.w.truncate();
+        let right = target.x.truncate().normalize();
+        let up = if self.settings.follow_roll { target.y.truncate().normalize() } else { Vector3::unit_y() };
+        let back = target.z.truncate().normalize();
+
+        let [x, y, z] = self.settings.offset;
+        let (offset, forward) = if self.look_behind {
+            (-right * x + up * y - back * z, back)
+        } else {
+            (right * x + up * y + back * z, -back)
+        };
+        let camera_position = position + offset;
+        let look_at = position + forward * self.settings.look_ahead;
+        let direction = (look_at - camera_position).normalize();
+
+        if self.cut {
+            self.cut = false;
+            self.position.snap(camera_position);
+            self.direction.snap(direction);
+            self.up.snap(up);
+        } else {
+            let (position_lag, rotation_lag) = (self.settings.position_lag, self.settings.rotation_lag);
+            self.position.update(camera_position, position_lag, time);
+            self.direction.update(direction, rotation_lag, time);
+            self.up.update(up, rotation_lag, time);
+        }
+        self.camera.look_along(self.position.value(), self.direction.value(), self.up.value());
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Matrix3};
+
+    #[test]
+    fn springs_approach_without_overshooting() {
+        let mut spring = Spring::new(Vector3::new(0.0, 0.0, 0.0));
+        let target = Vector3::new(1.0, 0.0, 0.0);
+        let mut previous = 0.0;
+        for _ in 0..120 {
+            let value = spring.update(target, 0.2, 1.0 / 60.0).x;
+            assert!(value >= previous && value <= 1.0);
+            previous = value;
+        }
+        assert!((spring.value() - target).magnitude() < 1e-3);
+        assert!((spring.update(Vector3::new(0.0, 2.0, 0.0), 0.0, 1.0 / 60.0) - Vector3::new(0.0, 2.0, 0.0)).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn camera_settles_behind_the_target() {
+        let camera = CameraState::new(Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mut chase = ChaseCamera::new(ChaseSettings { offset: [0.0, 1.0, 4.0], ..ChaseSettings::default() }, camera);
+        // turned to look along +x
+        let target = Matrix4::from_translation(Vector3::new(5.0, 0.0, 0.0)) * Matrix4::from_angle_y(Deg(-90.0));
+        chase.update(&target, 1.0 / 60.0);
+        assert!((chase.camera().position() - Vector3::new(1.0, 1.0, 0.0)).magnitude() < 1e-5);
+
+        // lags behind a moved target and catches up eventually
+        let moved = Matrix4::from_translation(Vector3::new(10.0, 0.0, 0.0)) * target;
+        chase.update(&moved, 1.0 / 60.0);
+        assert!(chase.camera().position().x < 5.0);
+        for _ in 0..120 {
+            chase.update(&moved, 1.0 / 60.0);
+        }
+        assert!((chase.camera().position() - Vector3::new(11.0, 1.0, 0.0)).magnitude() < 1e-2);
+
+        chase.set_look_behind(true);
+        chase.update(&moved, 1.0 / 60.0);
+        assert!((chase.camera().position() - Vector3::new(19.0, 1.0, 0.0)).magnitude() < 1e-5);
+        let orientation: Matrix3<f32> = chase.camera().orientation();
+        // the camera looks along its negative z axis, which points back along -x now
+        assert!((orientation.z - Vector3::new(1.0, 0.0, 0.0)).magnitude() < 0.2);
+    }
+}
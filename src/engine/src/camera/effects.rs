@@ -0,0 +1,263 @@
+use cgmath::{Deg, Quaternion, Rotation3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::CameraState;
+
+/// How strongly [`CameraEffects`] alter the view.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EffectSettings {
+    /// speed in units per second from which the field of view starts widening
+    pub fov_min_speed: f32,
+    /// speed in units per second at which the field of view is widest
+    pub fov_max_speed: f32,
+    /// degrees added to the field of view at `fov_max_speed`
+    pub fov_widening: f32,
+    /// time in seconds the field of view takes to follow changes of speed
+    pub fov_lag: f32,
+    /// rotation in degrees around each axis at full trauma
+    pub shake_angle: f32,
+    /// displacement in units along each axis at full trauma
+    pub shake_offset: f32,
+    /// how often per second the shake changes direction
+    pub shake_frequency: f32,
+    /// trauma lost per second
+    pub trauma_decay: f32,
+    /// trauma added per unit per second the speed changes by, e.g. when boosting or braking
+    pub boost_trauma: f32,
+    /// degrees the camera turns towards the inside of a turn at full turning rate
+    pub turn_angle: f32,
+    /// time in seconds the camera takes to follow changes of the turning rate
+    pub turn_lag: f32,
+}
+
+impl Default for EffectSettings {
+    fn default() -> Self {
+        Self {
+            fov_min_speed: 2.0,
+            fov_max_speed: 6.0,
+            fov_widening: 15.0,
+            fov_lag: 0.3,
+            shake_angle: 3.0,
+            shake_offset: 0.05,
+            shake_frequency: 15.0,
+            trauma_decay: 1.0,
+            boost_trauma: 0.25,
+            turn_angle: 8.0,
+            turn_lag: 0.25,
+        }
+    }
+}
+
+impl EffectSettings {
+
+    /// Returns the name of the first key having an invalid value.
+    pub(crate) fn invalid_key(&self) -> Option<&'static str> {
+        let valid = |value: f32| value >= 0.0 && value.is_finite();
+        [
+            ("fov_min_speed", self.fov_min_speed),
+            ("fov_max_speed", self.fov_max_speed),
+            ("fov_widening", self.fov_widening),
+            ("fov_lag", self.fov_lag),
+            ("shake_angle", self.shake_angle),
+            ("shake_offset", self.shake_offset),
+            ("shake_frequency", self.shake_frequency),
+            ("trauma_decay", self.trauma_decay),
+            ("boost_trauma", self.boost_trauma),
+            ("turn_angle", self.turn_angle),
+            ("turn_lag", self.turn_lag),
+        ].iter().find(|(_, value)| !valid(*value)).map(|(key, _)| *key)
+                .or_else(|| (self.fov_max_speed <= self.fov_min_speed).then_some("fov_max_speed"))
+    }
+
+}
+
+/// Effects conveying the motion of the camera: a field of view widening with speed, shaking caused by trauma, e.g.
+/// from collisions or landings, and turning into turns.
+///
+/// The effects are updated once per simulation tick and applied to the camera right before drawing, so they work
+/// with any camera and can be faded in and out by the weight passed to [`apply`](Self::apply).
+///
+/// Trauma is a value between 0.0 and 1.0 that decays linearly over time; the shake grows with its square, so small
+/// hits cause a barely noticeable shake while big ones shake the camera violently. The shake follows smooth noise, so
+/// it is the same whenever the same ticks are simulated, e.g. in replays.
+#[derive(Clone, Debug)]
+pub struct CameraEffects {
+    settings: EffectSettings,
+    /// degrees added to the field of view
+    widening: f32,
+    trauma: f32,
+    /// position along the noise driving the shake
+    phase: f32,
+    /// degrees the camera is turned around its up axis
+    turn: f32,
+}
+
+impl CameraEffects {
+
+    #[must_use]
+    pub fn new(settings: EffectSettings) -> Self {
+        Self { settings, widening: 0.0, trauma: 0.0, phase: 0.0, turn: 0.0 }
+    }
+
+    #[must_use]
+    pub fn settings(&self) -> &EffectSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: EffectSettings) {
+        self.settings = settings;
+    }
+
+    #[must_use]
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Adds trauma between 0.0 (nothing happened) and 1.0 (the biggest hit imaginable); the sum is capped at 1.0.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount.max(0.0)).min(1.0);
+    }
+
+    /// Stops all effects immediately, e.g. after the camera has been switched.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.settings.clone());
+    }
+
+    /// Advances the effects by `time` seconds for a camera moving at `speed` (units per second) and turning at
+    /// `turn_rate` (-1.0 for a full turn to the right to 1.0 for a full turn to the left).
+    pub fn update(&mut self, speed: f32, turn_rate: f32, time: f32) {
+        let settings = &self.settings;
+        let range = settings.fov_max_speed - settings.fov_min_speed;
+        let fraction = ((speed - settings.fov_min_speed) / range).clamp(0.0, 1.0);
+        self.widening = approach(self.widening, fraction * settings.fov_widening, settings.fov_lag, time);
+        self.turn = approach(self.turn, turn_rate.clamp(-1.0, 1.0) * settings.turn_angle, settings.turn_lag, time);
+
+        self.trauma = (self.trauma - settings.trauma_decay * time).max(0.0);
+        // wraps around where the noise repeats
+        self.phase = (self.phase + settings.shake_frequency * time) % NOISE_PERIOD;
+    }
+
+    /// Returns `camera` with the effects applied, scaled by `weight` from 0.0 (none) to 1.0 (full).
+    #[must_use]
+    pub fn apply(&self, camera: &CameraState, weight: f32) -> CameraState {
+        let mut result = camera.clone();
+        result.set_fovy(Deg((camera.fovy().0 + self.widening * weight).min(MAX_FOVY)));
+
+        let shake = self.trauma * self.trauma * weight;
+        let angle = |seed| Deg(self.settings.shake_angle * shake * noise(seed, self.phase));
+        let offset = |seed| self.settings.shake_offset * shake * noise(seed, self.phase);
+        let rotation = camera.rotation()
+                * Quaternion::from_angle_y(Deg(self.turn * weight) + angle(0))
+                * Quaternion::from_angle_x(angle(1))
+                * Quaternion::from_angle_z(angle(2));
+        result.set_rotation(rotation);
+        result.move_by(Vector3::new(offset(3), offset(4), offset(5)));
+        result
+    }
+
+}
+
+/// Widest field of view in degrees the effects may cause.
+const MAX_FOVY: f32 = 170.0;
+
+/// Number of noise values before the noise repeats itself.
+const NOISE_PERIOD: f32 = 65536.0;
+
+/// Moves `value` exponentially towards `target`, covering about two thirds of the distance within `lag` seconds.
+fn approach(value: f32, target: f32, lag: f32, time: f32) -> f32 {
+    if lag <= 0.0 {
+        return target;
+    }
+    target + (value - target) * (-time / lag).exp()
+}
+
+/// Smooth noise between -1.0 and 1.0 interpolating random values at whole numbers; `seed` selects one of many
+/// independent sequences.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn noise(seed: u32, phase: f32) -> f32 {
+    let floor = phase.floor();
+    let fraction = phase - floor;
+    let index = floor as u32;
+    let smooth = fraction * fraction * (3.0 - 2.0 * fraction);
+    let (start, end) = (lattice(seed, index), lattice(seed, index.wrapping_add(1)));
+    start + (end - start) * smooth
+}
+
+/// A random value between -1.0 and 1.0 for each whole number, repeating after [`NOISE_PERIOD`] numbers.
+#[allow(clippy::cast_precision_loss)]
+fn lattice(seed: u32, index: u32) -> f32 {
+    let mut hash = (index & 0xFFFF).wrapping_mul(0x9E37_79B9) ^ seed.wrapping_mul(0x85EB_CA6B);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7FEB_352D);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846C_A68B);
+    hash ^= hash >> 16;
+    (hash >> 8) as f32 / (1 << 23) as f32 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+
+    fn camera() -> CameraState {
+        CameraState::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0), 1.0)
+    }
+
+    #[test]
+    fn fov_widens_with_speed() {
+        let mut effects = CameraEffects::new(EffectSettings { fov_lag: 0.0, ..EffectSettings::default() });
+        effects.update(1.0, 0.0, 1.0 / 60.0);
+        assert!((effects.apply(&camera(), 1.0).fovy().0 - 90.0).abs() < 1e-4);
+        effects.update(4.0, 0.0, 1.0 / 60.0);
+        assert!((effects.apply(&camera(), 1.0).fovy().0 - 97.5).abs() < 1e-4);
+        assert!((effects.apply(&camera(), 0.5).fovy().0 - 93.75).abs() < 1e-4);
+        effects.update(100.0, 0.0, 1.0 / 60.0);
+        assert!((effects.apply(&camera(), 1.0).fovy().0 - 105.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn trauma_shakes_until_it_has_decayed() {
+        let mut effects = CameraEffects::new(EffectSettings::default());
+        effects.add_trauma(0.7);
+        effects.add_trauma(0.7);
+        assert!((effects.trauma() - 1.0).abs() < 1e-6);
+
+        let mut moved = false;
+        for _ in 0..30 {
+            effects.update(0.0, 0.0, 1.0 / 60.0);
+            moved |= effects.apply(&camera(), 1.0).position().magnitude() > 1e-3;
+        }
+        assert!(moved);
+        assert!((effects.apply(&camera(), 0.0).position()).magnitude() < 1e-6);
+
+        for _ in 0..60 {
+            effects.update(0.0, 0.0, 1.0 / 60.0);
+        }
+        assert!(effects.trauma() < 1e-6);
+        assert!(effects.apply(&camera(), 1.0).position().magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn camera_looks_into_turns() {
+        let mut effects = CameraEffects::new(EffectSettings { turn_lag: 0.0, ..EffectSettings::default() });
+        effects.update(0.0, 1.0, 1.0 / 60.0);
+        // turning left makes the camera look a bit to the left
+        let forward = effects.apply(&camera(), 1.0).orientation().z * -1.0;
+        assert!(forward.x < -0.1 && forward.z < -0.9);
+    }
+
+    #[test]
+    fn noise_is_smooth_and_bounded() {
+        let mut previous = noise(7, 0.0);
+        for step in 1..10_000_u16 {
+            let value = noise(7, f32::from(step) * 0.01);
+            assert!((-1.0..=1.0).contains(&value));
+            assert!((value - previous).abs() < 0.05);
+            previous = value;
+        }
+        // just as smooth where the noise repeats
+        assert!((noise(3, NOISE_PERIOD - 0.01) - noise(3, 0.0)).abs() < 0.05);
+    }
+}
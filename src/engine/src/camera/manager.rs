@@ -0,0 +1,335 @@
+use cgmath::{Deg, Matrix3, Matrix4, Vector3};
+
+use crate::camera::{CameraState, ChaseCamera};
+use crate::rotation;
+use crate::scene::Transform;
+
+/// How a camera of a [`CameraManager`] moves along with the target.
+#[derive(Clone)]
+pub enum CameraRig {
+    /// moved by the game itself, e.g. a free-flying debug camera
+    Free(CameraState),
+    /// rigidly attached to the target at `offset`, like a cockpit or bumper camera
+    Mounted { camera: CameraState, offset: Transform },
+    /// follows the target from behind
+    Chase(ChaseCamera),
+    /// stays in place and turns to keep the target in view, like a track-side TV camera
+    Tracking(CameraState),
+}
+
+impl CameraRig {
+
+    /// The camera as of the last update.
+    #[must_use]
+    pub fn camera(&self) -> &CameraState {
+        match self {
+            CameraRig::Free(camera) | CameraRig::Mounted { camera, .. } | CameraRig::Tracking(camera) => camera,
+            CameraRig::Chase(chase) => chase.camera(),
+        }
+    }
+
+    /// Gives access to the camera; what the rig controls is overwritten by the next update.
+    pub fn camera_mut(&mut self) -> &mut CameraState {
+        match self {
+            CameraRig::Free(camera) | CameraRig::Mounted { camera, .. } | CameraRig::Tracking(camera) => camera,
+            CameraRig::Chase(chase) => chase.camera_mut(),
+        }
+    }
+
+    /// Follows `target`, the matrix transforming the target's coordinate system into world space, for `time` seconds.
+    pub fn update(&mut self, target: &Matrix4<f32>, time: f32) {
+        match self {
+            CameraRig::Free(_) => {},
+            CameraRig::Mounted { camera, offset } => {
+                let mount = target * offset.matrix();
+                let axes = Matrix3::from_cols(mount.x.truncate(), mount.y.truncate(), mount.z.truncate());
+                camera.set_position(mount.w.truncate());
+                camera.set_rotation(rotation::from_axes(axes));
+            },
+            CameraRig::Chase(chase) => chase.update(target, time),
+            CameraRig::Tracking(camera) => {
+                let position = camera.position();
+                camera.look_along(position, target.w.truncate() - position, Vector3::unit_y());
+            },
+        }
+    }
+
+}
+
+/// Identifies a camera within its [`CameraManager`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CameraId(usize);
+
+struct Entry {
+    name: String,
+    rig: CameraRig,
+}
+
+/// A transition from one view to another.
+struct Blend {
+    /// the camera blended out if it still exists; otherwise `from_view` stays where it was
+    from: Option<CameraId>,
+    from_view: CameraState,
+    elapsed: f32,
+    duration: f32,
+}
+
+/// A camera shown instead of the active one for a while.
+struct Temporary {
+    rig: CameraRig,
+    /// seconds until the camera ends by itself, if ever
+    remaining: Option<f32>,
+    /// duration of the transition back to the active camera when it ends by itself
+    blend_out: f32,
+}
+
+/// Several named cameras following the same target, one of which is active.
+///
+/// All cameras are updated every tick, so switching between them is seamless; transitions may blend position,
+/// orientation and field of view smoothly over a given time. Game code may also show a temporary camera, e.g. for a
+/// replay of the finish, which takes precedence over the active camera until it ends.
+#[allow(clippy::module_name_repetitions)]
+pub struct CameraManager {
+    cameras: Vec<Entry>,
+    active: CameraId,
+    temporary: Option<Temporary>,
+    blend: Option<Blend>,
+    /// the view as of the last update
+    view: CameraState,
+    /// the view before the last update; used for interpolation
+    previous_view: CameraState,
+    /// whether the view jumped since the last update, so there's nothing to interpolate from
+    cut: bool,
+}
+
+impl CameraManager {
+
+    /// Creates a manager having a single camera, which is active.
+    #[must_use]
+    pub fn new(name: &str, rig: CameraRig) -> Self {
+        let view = rig.camera().clone();
+        Self {
+            cameras: vec![Entry { name: name.to_string(), rig }],
+            active: CameraId(0),
+            temporary: None,
+            blend: None,
+            previous_view: view.clone(),
+            view,
+            cut: false,
+        }
+    }
+
+    /// Adds a camera, which comes last when cycling through the cameras.
+    pub fn add(&mut self, name: &str, rig: CameraRig) -> CameraId {
+        self.cameras.push(Entry { name: name.to_string(), rig });
+        CameraId(self.cameras.len() - 1)
+    }
+
+    /// Returns the first camera having the given name.
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<CameraId> {
+        self.cameras.iter().position(|entry| entry.name == name).map(CameraId)
+    }
+
+    #[must_use]
+    pub fn name(&self, id: CameraId) -> &str {
+        &self.cameras[id.0].name
+    }
+
+    #[must_use]
+    pub fn rig(&self, id: CameraId) -> &CameraRig {
+        &self.cameras[id.0].rig
+    }
+
+    pub fn rig_mut(&mut self, id: CameraId) -> &mut CameraRig {
+        &mut self.cameras[id.0].rig
+    }
+
+    /// The camera that is shown unless a temporary camera is shown instead.
+    #[must_use]
+    pub fn active(&self) -> CameraId {
+        self.active
+    }
+
+    /// Whether a temporary camera is shown instead of the active one.
+    #[must_use]
+    pub fn has_temporary(&self) -> bool {
+        self.temporary.is_some()
+    }
+
+    /// Makes a camera active, blending over to it within `blend` seconds; 0.0 cuts to it immediately.
+    ///
+    /// While a temporary camera is shown, the new camera is shown only after that has ended.
+    pub fn activate(&mut self, id: CameraId, blend: f32) {
+        if id == self.active {
+            return;
+        }
+        if self.temporary.is_none() {
+            self.start_blend(Some(self.active), blend);
+        }
+        self.active = id;
+    }
+
+    /// Activates the camera `steps` places after the active one, wrapping around after the last one.
+    pub fn cycle(&mut self, steps: usize, blend: f32) {
+        self.activate(CameraId((self.active.0 + steps) % self.cameras.len()), blend);
+    }
+
+    /// Shows `rig` instead of the active camera, blending over to it within `blend` seconds.
+    ///
+    /// The temporary camera ends by itself after `duration` seconds if given, blending back within `blend` seconds,
+    /// or when [`end_temporary`](Self::end_temporary) is called. It replaces any other temporary camera.
+    pub fn show_temporary(&mut self, rig: CameraRig, duration: Option<f32>, blend: f32) {
+        let from = if self.temporary.is_some() { None } else { Some(self.active) };
+        self.start_blend(from, blend);
+        self.temporary = Some(Temporary { rig, remaining: duration, blend_out: blend });
+    }
+
+    /// Ends the temporary camera, if any, and blends back to the active camera within `blend` seconds.
+    pub fn end_temporary(&mut self, blend: f32) {
+        if self.temporary.take().is_some() {
+            self.start_blend(None, blend);
+        }
+    }
+
+    /// Sets the aspect ratio (width / height) of the viewport for all cameras.
+    pub fn set_aspect_ratio(&mut self, ratio: f32) {
+        for entry in &mut self.cameras {
+            entry.rig.camera_mut().set_aspect_ratio(ratio);
+        }
+        if let Some(temporary) = &mut self.temporary {
+            temporary.rig.camera_mut().set_aspect_ratio(ratio);
+        }
+        if let Some(blend) = &mut self.blend {
+            blend.from_view.set_aspect_ratio(ratio);
+        }
+        self.view.set_aspect_ratio(ratio);
+        self.previous_view.set_aspect_ratio(ratio);
+    }
+
+    /// Moves all cameras along with `target` (see [`CameraRig::update`]) for `time` seconds and advances transitions.
+    pub fn update(&mut self, target: &Matrix4<f32>, time: f32) {
+        self.previous_view = self.view.clone();
+        for entry in &mut self.cameras {
+            entry.rig.update(target, time);
+        }
+
+        let mut expired = None;
+        if let Some(temporary) = &mut self.temporary {
+            temporary.rig.update(target, time);
+            if let Some(remaining) = &mut temporary.remaining {
+                *remaining -= time;
+                if *remaining <= 0.0 {
+                    expired = Some(temporary.blend_out);
+                }
+            }
+        }
+        if let Some(blend_out) = expired {
+            self.end_temporary(blend_out);
+        }
+
+        let target_view = match &self.temporary {
+            Some(temporary) => temporary.rig.camera(),
+            None => self.cameras[self.active.0].rig.camera(),
+        };
+        self.view = match &mut self.blend {
+            Some(blend) => {
+                blend.elapsed += time;
+                if let Some(from) = blend.from {
+                    blend.from_view = self.cameras[from.0].rig.camera().clone();
+                }
+                let fraction = (blend.elapsed / blend.duration).min(1.0);
+                let weight = fraction * fraction * (3.0 - 2.0 * fraction);
+                let mut view = target_view.interpolated(&blend.from_view, weight);
+                let (from_fovy, to_fovy) = (blend.from_view.fovy().0, target_view.fovy().0);
+                view.set_fovy(Deg(from_fovy + (to_fovy - from_fovy) * weight));
+                view
+            },
+            None => target_view.clone(),
+        };
+        if self.blend.as_ref().is_some_and(|blend| blend.elapsed >= blend.duration) {
+            self.blend = None;
+        }
+
+        if self.cut {
+            self.cut = false;
+            self.previous_view = self.view.clone();
+        }
+    }
+
+    /// The view between the last two updates, from `alpha` = 0.0 for the previous one to 1.0 for the last one.
+    #[must_use]
+    pub fn view(&self, alpha: f32) -> CameraState {
+        self.view.interpolated(&self.previous_view, alpha)
+    }
+
+    /// Starts a transition from the current view, following the camera `from` if given, unless `duration` is 0.0.
+    fn start_blend(&mut self, from: Option<CameraId>, duration: f32) {
+        // a transition interrupting another one starts where the view is right now
+        let from = if self.blend.is_some() { None } else { from };
+        self.blend = if duration > 0.0 {
+            Some(Blend { from, from_view: self.view.clone(), elapsed: 0.0, duration })
+        } else {
+            self.cut = true;
+            None
+        };
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{InnerSpace, SquareMatrix};
+
+    fn camera_at(x: f32) -> CameraState {
+        CameraState::new(Vector3::new(x, 0.0, 0.0), Vector3::new(x, 0.0, -1.0), 1.0)
+    }
+
+    #[test]
+    fn cameras_are_cycled_and_blended() {
+        let mut cameras = CameraManager::new("left", CameraRig::Free(camera_at(0.0)));
+        let right = cameras.add("right", CameraRig::Free(camera_at(10.0)));
+        assert_eq!(cameras.find("right"), Some(right));
+        let target = Matrix4::identity();
+
+        cameras.cycle(3, 1.0);
+        assert_eq!(cameras.name(cameras.active()), "right");
+        cameras.update(&target, 0.5);
+        assert!((cameras.view(1.0).position() - Vector3::new(5.0, 0.0, 0.0)).magnitude() < 1e-4);
+        // the view is interpolated between updates
+        assert!(cameras.view(0.0).position().magnitude() < 1e-4);
+        cameras.update(&target, 0.5);
+        assert!((cameras.view(1.0).position() - Vector3::new(10.0, 0.0, 0.0)).magnitude() < 1e-4);
+
+        cameras.cycle(1, 0.0);
+        cameras.update(&target, 0.5);
+        assert!(cameras.view(0.0).position().magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn temporary_cameras_end_by_themselves() {
+        let mut cameras = CameraManager::new("main", CameraRig::Free(camera_at(0.0)));
+        let target = Matrix4::from_translation(Vector3::new(0.0, 0.0, -10.0));
+        cameras.show_temporary(CameraRig::Tracking(camera_at(10.0)), Some(1.0), 0.0);
+        assert!(cameras.has_temporary());
+        cameras.update(&target, 0.5);
+        let view = cameras.view(1.0);
+        assert!((view.position() - Vector3::new(10.0, 0.0, 0.0)).magnitude() < 1e-4);
+        // turned towards the target
+        assert!((view.orientation().z - Vector3::new(1.0, 0.0, 1.0).normalize()).magnitude() < 1e-4);
+
+        cameras.update(&target, 0.5);
+        assert!(!cameras.has_temporary());
+        assert!(cameras.view(0.0).position().magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn mounted_cameras_move_with_the_target() {
+        let offset = Transform::from_translation(Vector3::new(0.0, 1.0, 0.0));
+        let mut rig = CameraRig::Mounted { camera: camera_at(0.0), offset };
+        rig.update(&(Matrix4::from_translation(Vector3::new(3.0, 0.0, 0.0)) * Matrix4::from_angle_z(Deg(90.0))), 0.1);
+        assert!((rig.camera().position() - Vector3::new(2.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!((rig.camera().orientation().y - Vector3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-5);
+    }
+}
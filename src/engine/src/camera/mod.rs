@@ -0,0 +1,206 @@
+use crate::input::Control;
+use crate::rotation;
+use cgmath::{Deg, InnerSpace, Matrix, Matrix3, Matrix4, PerspectiveFov, Quaternion, Rad, Rotation3, Vector3, Vector4, VectorSpace};
+
+pub use chase::{ChaseCamera, ChaseSettings, Spring};
+pub use effects::{CameraEffects, EffectSettings};
+pub use manager::{CameraId, CameraManager, CameraRig};
+
+mod chase;
+mod effects;
+mod manager;
+
+/// A free-flying camera described by its position and orientation in world space.
+///
+/// The orientation is kept as unit quaternion, which is interpolated along the shortest arc between two states.
+#[derive(Clone)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
+pub struct CameraState {
+    aspect_ratio: f32,
+    fovy: Deg<f32>,
+    near: f32,
+    far: f32,
+    position: Vector3<f32>,
+    /// turns the camera's own axes into world space
+    rotation: Quaternion<f32>,
+}
+
+impl CameraState {
+    /// Creates a camera located at `position` looking towards `look_at` with the world's y axis pointing upwards.
+    #[must_use]
+    pub fn new(position: Vector3<f32>, look_at: Vector3<f32>, aspect_ratio: f32) -> CameraState {
+
+        // depth vector of the camery pointing into the observer's eye
+        let cam_z = (position - look_at).normalize();
+        // right vector of the camera
+        let cam_x = Vector3::<f32>::unit_y().cross(cam_z).normalize();
+        // up vector of the camera
+        let cam_y = cam_z.cross(cam_x);
+
+        CameraState {
+            aspect_ratio,
+            fovy: Deg(90.0),
+            near: 0.1,
+            far: 1024.0,
+            position,
+            rotation: rotation::from_axes(Matrix3::from_cols(cam_x, cam_y, cam_z)),
+        }
+    }
+
+    /// Updates the aspect ratio (width / height) of the viewport; usually called after the window has been resized.
+    pub fn set_aspect_ratio(&mut self, ratio: f32) {
+        self.aspect_ratio = ratio;
+    }
+
+    /// Sets the vertical field of view and the distances of the near and far clipping planes.
+    pub fn set_projection(&mut self, fovy: Deg<f32>, near: f32, far: f32) {
+        self.fovy = fovy;
+        self.near = near;
+        self.far = far;
+    }
+
+    /// The vertical field of view.
+    #[must_use]
+    pub fn fovy(&self) -> Deg<f32> {
+        self.fovy
+    }
+
+    pub fn set_fovy(&mut self, fovy: Deg<f32>) {
+        self.fovy = fovy;
+    }
+
+    /// The position of the camera in world space.
+    #[must_use]
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Vector3<f32>) {
+        self.position = position;
+    }
+
+    /// The axes of the camera in world space: right, up and pointing into the observer's eye.
+    #[must_use]
+    pub fn orientation(&self) -> Matrix3<f32> {
+        rotation::axes(self.rotation)
+    }
+
+    /// The orientation of the camera in world space as unit quaternion.
+    #[must_use]
+    pub fn rotation(&self) -> Quaternion<f32> {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: Quaternion<f32>) {
+        self.rotation = rotation.normalize();
+    }
+
+    /// Moves the camera to `position` and turns it to look along `direction` with `up` pointing upwards as far as
+    /// possible. The orientation around `direction` is kept if `up` is parallel to it.
+    pub fn look_along(&mut self, position: Vector3<f32>, direction: Vector3<f32>, up: Vector3<f32>) {
+        self.position = position;
+        let current = self.orientation();
+        // falling back to the current axes, one of which is never parallel to the direction
+        if let Some(rotation) = rotation::look_rotation(direction, up)
+                .or_else(|| rotation::look_rotation(direction, current.y))
+                .or_else(|| rotation::look_rotation(direction, current.z)) {
+            self.rotation = rotation;
+        }
+    }
+
+    /// Returns the projection matrix transforming camera space into clip space.
+    #[must_use]
+    pub fn get_perspective(&self) -> Matrix4<f32> {
+        PerspectiveFov {
+            fovy: self.fovy.into(),
+            aspect: self.aspect_ratio,
+            near: self.near,
+            far: self.far,
+        }.into()
+    }
+
+    /// Returns the view matrix transforming world space into camera space.
+    #[must_use]
+    pub fn get_view(&self) -> Matrix4<f32> {
+        view_matrix(self.position, self.orientation())
+    }
+
+    /// Returns the view matrix of a state between `previous` (`alpha` = 0.0) and `self` (`alpha` = 1.0).
+    #[must_use]
+    pub fn get_interpolated_view(&self, previous: &Self, alpha: f32) -> Matrix4<f32> {
+        self.interpolated(previous, alpha).get_view()
+    }
+
+    /// Returns a state between `previous` (`alpha` = 0.0) and `self` (`alpha` = 1.0) with the projection of `self`.
+    #[must_use]
+    pub fn interpolated(&self, previous: &Self, alpha: f32) -> Self {
+        Self {
+            position: previous.position.lerp(self.position, alpha),
+            rotation: rotation::slerp(previous.rotation, self.rotation, alpha),
+            ..self.clone()
+        }
+    }
+
+    /// Moves the camera by `movement` given in the camera's own coordinate system.
+    pub fn move_by(&mut self, movement: Vector3<f32>) {
+        self.position += self.rotation * movement;
+    }
+    
+    /// Rotates the camera around its own up axis.
+    pub fn yaw(&mut self, angle: Rad<f32>) {
+        self.rotation = (self.rotation * Quaternion::from_angle_y(angle)).normalize();
+    }
+    
+    /// Rotates the camera around its own right axis.
+    pub fn pitch(&mut self, angle: Rad<f32>) {
+        self.rotation = (self.rotation * Quaternion::from_angle_x(angle)).normalize();
+    }
+    
+    /// Rotates the camera around its own depth axis.
+    pub fn roll(&mut self, angle: Rad<f32>) {
+        self.rotation = (self.rotation * Quaternion::from_angle_z(angle)).normalize();
+    }
+
+    /// Turns the camera by `angular_velocity` (radians per second around its own right, up and depth axes) for
+    /// `time` seconds.
+    pub fn rotate(&mut self, angular_velocity: Vector3<f32>, time: f32) {
+        self.rotation = rotation::integrate(self.rotation, angular_velocity, time);
+    }
+    
+    /// Moves and rotates the camera according to the current state of `control` for a time span of `time` seconds.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn update_position(&mut self, control: &Control, time: f32) {
+
+        // units per second
+        let move_speed = 6.0 * time;
+        // radians per second
+        let rotate_speed = 6.0;
+
+        let dy = control.ascend.value;
+        let dx = control.strafe.value;
+        let dz = -control.thrust.value;
+
+        let yaw = control.yaw.value;
+        let pitch = control.pitch.value;
+        let roll = control.roll.value;
+
+        // describes the movement of the camera in it's own coordinate system
+        let half_movement = Vector3::new(dx, dy, dz) * move_speed * 0.5;
+
+        // all three rotations at once around the camera's own axes, which doesn't depend on their order
+        let angular_velocity = Vector3::new(pitch, yaw, roll) * rotate_speed;
+
+        //perform a move/2→rotate→move/2 sequence which should be more precise than move→rotate or rotate→move
+        self.move_by(half_movement);
+        self.rotate(angular_velocity, time);
+        self.move_by(half_movement);
+    }
+
+}
+
+fn view_matrix(position: Vector3<f32>, matrix: Matrix3<f32>) -> Matrix4<f32> {
+    let mut result = Matrix4::from(matrix.transpose());
+    // position of the world's origin in the camera's coordinate space
+    result.w = Vector4::new(-position.dot(matrix.x), -position.dot(matrix.y), -position.dot(matrix.z), 1.0);
+    result
+}
@@ -0,0 +1,288 @@
+use std::{error, fmt, fs, io};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use directories::ProjectDirs;
+use glium::backend::Facade;
+use glium::framebuffer::{DepthRenderBuffer, RenderBufferCreationError, SimpleFrameBuffer, ValidationError};
+use glium::texture::{DepthFormat, MipmapsOption, RawImage2d, Texture2d, TextureCreationError, UncompressedFloatFormat};
+use image::{ImageFormat, RgbaImage};
+use log::debug;
+
+/// Everything that went wrong while rendering a frame offscreen or saving it.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// The color texture could not be created
+    Texture(TextureCreationError),
+    /// The depth buffer could not be created
+    DepthBuffer(RenderBufferCreationError),
+    /// The texture and the depth buffer could not be combined into a frame buffer
+    FrameBuffer(ValidationError),
+    /// The image file could not be written
+    Io(io::Error),
+    /// The image could not be encoded
+    Encode(image::ImageError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Texture(err) => write!(f, "could not create color texture: {err}"),
+            Self::DepthBuffer(err) => write!(f, "could not create depth buffer: {err}"),
+            Self::FrameBuffer(err) => write!(f, "could not create frame buffer: {err}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Encode(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Texture(err) => Some(err),
+            Self::DepthBuffer(err) => Some(err),
+            Self::FrameBuffer(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Encode(err) => Some(err),
+        }
+    }
+}
+
+impl From<TextureCreationError> for CaptureError {
+    fn from(err: TextureCreationError) -> Self {
+        Self::Texture(err)
+    }
+}
+
+impl From<RenderBufferCreationError> for CaptureError {
+    fn from(err: RenderBufferCreationError) -> Self {
+        Self::DepthBuffer(err)
+    }
+}
+
+impl From<ValidationError> for CaptureError {
+    fn from(err: ValidationError) -> Self {
+        Self::FrameBuffer(err)
+    }
+}
+
+impl From<io::Error> for CaptureError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<image::ImageError> for CaptureError {
+    fn from(err: image::ImageError) -> Self {
+        Self::Encode(err)
+    }
+}
+
+/// A color texture along with a depth buffer to render frames into instead of a window.
+///
+/// This works with any facade, including the headless ones created by [`crate::display::create_headless`].
+pub struct Offscreen {
+    color: Texture2d,
+    depth: DepthRenderBuffer,
+}
+
+impl Offscreen {
+
+    /// Creates a render target of `width` × `height` pixels.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the OpenGL context doesn't support the required formats.
+    pub fn new<F>(facade: &F, width: u32, height: u32) -> Result<Self, CaptureError>
+    where
+        F: Facade + ?Sized
+    {
+        let color = Texture2d::empty_with_format(facade, UncompressedFloatFormat::U8U8U8U8, MipmapsOption::NoMipmap, width, height)?;
+        let depth = DepthRenderBuffer::new(facade, DepthFormat::I24, width, height)?;
+        Ok(Self { color, depth })
+    }
+
+    /// Size of the rendered frames in pixels.
+    #[must_use]
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.color.width(), self.color.height())
+    }
+
+    /// Lets `draw` render a frame and returns the result as image with the top row first.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the frame buffer cannot be set up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the OpenGL driver returns less pixel data than the texture holds.
+    pub fn render<F, D>(&self, facade: &F, draw: D) -> Result<RgbaImage, CaptureError>
+    where
+        F: Facade + ?Sized,
+        D: FnOnce(&mut SimpleFrameBuffer<'_>),
+    {
+        let mut target = SimpleFrameBuffer::with_depth_buffer(facade, &self.color, &self.depth)?;
+        draw(&mut target);
+
+        let pixels: RawImage2d<'_, u8> = self.color.read();
+        let mut image = RgbaImage::from_raw(pixels.width, pixels.height, pixels.data.into_owned())
+                .expect("texture data doesn't match its dimensions");
+        // OpenGL stores the bottom row first
+        image::imageops::flip_vertical_in_place(&mut image);
+        Ok(image)
+    }
+
+}
+
+/// Writes `image` as PNG file to `path`, creating missing parent directories.
+///
+/// # Errors
+///
+/// Fails if the file cannot be written.
+pub fn save_png(image: &RgbaImage, path: &Path) -> Result<(), CaptureError> {
+    debug!("save image to {}", path.display());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    image.save_with_format(path, ImageFormat::Png)?;
+    Ok(())
+}
+
+/// Returns the largest difference of any color channel between `expected` and `actual`, or `None` if their sizes
+/// differ.
+///
+/// Meant for golden-image tests, which have to tolerate small deviations between OpenGL implementations.
+#[must_use]
+pub fn max_difference(expected: &RgbaImage, actual: &RgbaImage) -> Option<u8> {
+    if expected.dimensions() != actual.dimensions() {
+        return None;
+    }
+    let difference = expected.as_raw().iter()
+            .zip(actual.as_raw().iter())
+            .map(|(&expected, &actual)| expected.abs_diff(actual))
+            .max()
+            .unwrap_or(0);
+    Some(difference)
+}
+
+/// Returns the platform specific directory for the screenshots of `application` (e.g.
+/// `~/.local/share/<application>/screenshots` on Linux).
+#[must_use]
+pub fn default_dir(application: &str) -> Option<PathBuf> {
+    ProjectDirs::from("", "", application).map(|dirs| dirs.data_local_dir().join("screenshots"))
+}
+
+/// Returns a path within `dir` for a new screenshot named after the current time that doesn't exist yet.
+#[must_use]
+pub fn screenshot_path(dir: &Path) -> PathBuf {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let mut path = dir.join(format!("screenshot-{seconds}.png"));
+    let mut index = 1;
+    while path.exists() {
+        path = dir.join(format!("screenshot-{seconds}-{index}.png"));
+        index += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::CameraState;
+    use crate::display;
+    use crate::model::load_stl;
+    use crate::settings::Settings;
+    use cgmath::Vector3;
+    use glium::{Surface, program, uniform};
+    use image::Rgba;
+    use std::io::Cursor;
+
+    /// Size of the golden images in pixels
+    const GOLDEN_SIZE: u32 = 128;
+
+    /// Largest difference of a color channel tolerated between OpenGL implementations
+    const GOLDEN_TOLERANCE: u8 = 2;
+
+    /// Renders `res/axis.stl` as seen from the game's initial camera position, colored by its normals.
+    fn render_axis<F: Facade + ?Sized>(facade: &F) -> RgbaImage {
+        let mesh = load_stl(&mut Cursor::new(&include_bytes!("../../../res/axis.stl")[..])).unwrap();
+        let buffers = mesh.upload(facade).unwrap();
+        let program = program!(facade,
+            140 => {
+                vertex: "
+                    #version 140
+                    uniform mat4 persp_matrix;
+                    uniform mat4 view_matrix;
+                    in vec3 position;
+                    in vec3 normal;
+                    out vec3 v_normal;
+                    void main() {
+                        v_normal = normal;
+                        gl_Position = persp_matrix * view_matrix * vec4(position, 1.0);
+                    }
+                ",
+                fragment: "
+                    #version 140
+                    in vec3 v_normal;
+                    out vec4 f_color;
+                    void main() {
+                        f_color = vec4(normalize(v_normal) * 0.5 + 0.5, 1.0);
+                    }
+                ",
+            },
+        ).unwrap();
+
+        let camera = CameraState::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let persp_matrix: [[f32; 4]; 4] = camera.get_perspective().into();
+        let view_matrix: [[f32; 4]; 4] = camera.get_view().into();
+        let params = glium::DrawParameters {
+            depth: glium::Depth { test: glium::DepthTest::IfLess, write: true, ..glium::Depth::default() },
+            ..glium::DrawParameters::default()
+        };
+
+        let offscreen = Offscreen::new(facade, GOLDEN_SIZE, GOLDEN_SIZE).unwrap();
+        offscreen.render(facade, |target| {
+            target.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+            target.draw(&buffers.vertices, &buffers.indices, &program, &uniform! { persp_matrix: persp_matrix, view_matrix: view_matrix }, &params).unwrap();
+        }).unwrap()
+    }
+
+    /// Compares the rendered `res/axis.stl` with `res/golden/axis.png`, which has been rendered by Mesa's llvmpipe.
+    ///
+    /// Run with `UPDATE_GOLDEN_IMAGES=1` to replace the reference image after intended changes to the rendering, and
+    /// with `SKIP_GL_TESTS=1` on machines without OpenGL.
+    #[test]
+    fn axis_matches_golden_image() {
+        let settings = Settings::default();
+        let headless = match display::create_headless(&settings) {
+            Ok(headless) => headless,
+            Err(err) if std::env::var_os("SKIP_GL_TESTS").is_some() => {
+                eprintln!("skipping golden-image test without OpenGL: {err}");
+                return;
+            },
+            Err(err) => panic!("no OpenGL context for the golden-image test (set SKIP_GL_TESTS=1 to skip it): {}", err),
+        };
+        let actual = render_axis(&headless);
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../res/golden/axis.png");
+        if std::env::var_os("UPDATE_GOLDEN_IMAGES").is_some() {
+            save_png(&actual, &path).unwrap();
+        }
+        let expected = image::open(&path).unwrap().into_rgba8();
+        let difference = max_difference(&expected, &actual).expect("image size differs from the golden image");
+        assert!(difference <= GOLDEN_TOLERANCE, "rendered image differs from {} by up to {}", path.display(), difference);
+    }
+
+    #[test]
+    fn difference_of_images() {
+        let expected = RgbaImage::from_pixel(4, 2, Rgba([10, 20, 30, 255]));
+        let mut actual = expected.clone();
+        assert_eq!(max_difference(&expected, &actual), Some(0));
+
+        actual.put_pixel(3, 1, Rgba([10, 25, 27, 255]));
+        assert_eq!(max_difference(&expected, &actual), Some(5));
+
+        assert_eq!(max_difference(&expected, &RgbaImage::new(2, 4)), None);
+    }
+}
@@ -0,0 +1,232 @@
+use glium::glutin::{ContextBuilder, CreationError, GlProfile, GlRequest, NotCurrent, Robustness, event_loop::EventLoop, dpi::{PhysicalSize, Size}, window::WindowBuilder};
+use glium::backend::{Context, Facade};
+use glium::backend::glutin::DisplayCreationError;
+use glium::{HeadlessRenderer, IncompatibleOpenGl};
+use log::debug;
+
+use crate::settings::Settings;
+
+use std::{error, fmt};
+use std::ops::Deref;
+use std::rc::Rc;
+use std::string::ToString;
+
+// Version details about WebGL 2.0 - Source: https://en.wikipedia.org/wiki/WebGL#Design
+// "WebGL 2.0 is based on OpenGL ES 3.0"
+
+// Version details about OpenGL ES 3.0 - Source: https://en.wikipedia.org/wiki/OpenGL_ES#OpenGL_ES_3.0
+// "OpenGL 4.3 provides full compatibility with OpenGL ES 3.0. Version 3.0 is also the basis for WebGL 2.0. Actual is version 3.0.6."
+
+// Version details about OpenGL ES 3.0 Shading Language - Source: https://en.wikipedia.org/wiki/OpenGL_Shading_Language#Versions
+// GLSL ES version: 3.00.6
+// OpenGL ES version: 3.0
+// WebGL version: 2.0
+// Based on GLSL version: 3.30
+// date: 29 January 2016
+// Shader Preprocessor: #version 300 es 
+
+// Version details about GLSL 3.30 - Source: https://en.wikipedia.org/wiki/OpenGL_Shading_Language#Versions
+// GLSL version: 3.30.6
+// OpenGL Version: 3.3
+// date: 11 March 2010
+// Shader Preprocessor: #version 330 
+
+// Version details about OpenGL 4.3 - Source: https://en.wikipedia.org/wiki/OpenGL#OpenGL_4.3
+// Version: 4.3
+// Release Date: August 6, 2012
+// Features: GLSL 4.30, … , compatibility with OpenGL ES 3.0
+
+// Version details about GLSL 4.3 - Source: https://en.wikipedia.org/wiki/OpenGL_Shading_Language#Versions
+// GLSL Version: 4.30.8
+// OpenGL version: 4.3
+// Date: 7 February 2013
+// Shader Preprocessor: #version 430 
+
+// Summing this mess up we need to stay compatible with (most restricted API first):
+// WebGL 2.0
+// OpenGL ES 3.0 with GLSL ES 3.0 (aka `#version 300 es`)
+// OpenGL 4.3 with GLSL 4.3 (aka `#version 430`)
+
+
+#[allow(clippy::doc_markdown)]
+/// The OpenGL version to use; WebGL 2.0 is based on OpenGL ES 3.0 which is based on OpenGL 4.3
+const GL_VERSION: (u8 ,u8) = (4, 3);
+const GLES_VERSION: (u8 ,u8) = (3, 0);
+const GL_REQUEST: GlRequest = GlRequest::GlThenGles{ opengl_version: GL_VERSION, opengles_version: GLES_VERSION };
+
+/// The OpenGL profile to use; we play it safe for now
+const GL_PROFILE: GlProfile = GlProfile::Core;
+
+/// What errors to check for and what to do in an emergency situation. We try to catch everything for now.
+const GL_ROBUSTNESS: Robustness = Robustness::TryRobustNoResetNotification;
+
+
+/// Opens a window and creates an OpenGL context matching the engine's requirements and the given `settings`.
+///
+/// # Errors
+///
+/// Fails if either the window or a compatible OpenGL context cannot be created.
+#[allow(clippy::module_name_repetitions)]
+pub fn create<T>(event_loop: &EventLoop<T>, settings: &Settings) -> Result<glium::Display, DisplayCreationError> {
+
+    let size = Size::Physical(PhysicalSize::new(settings.window.width, settings.window.height));
+    let window_builder = WindowBuilder::new()
+            .with_inner_size(size)
+            ;
+    let context_builder = ContextBuilder::new()
+            .with_gl(GL_REQUEST)
+            .with_gl_profile(GL_PROFILE)
+            .with_gl_robustness(GL_ROBUSTNESS)
+            .with_vsync(settings.graphics.vsync)
+            .with_multisampling(settings.graphics.multisampling)
+            .with_depth_buffer(settings.graphics.depth_buffer_bits)
+            ;
+
+    glium::Display::new(window_builder, context_builder, event_loop)
+}
+
+/// Everything that went wrong while creating a headless display.
+#[derive(Debug)]
+pub enum HeadlessError {
+    /// No OpenGL context matching the engine's requirements could be created
+    Creation(CreationError),
+    /// The OpenGL context lacks features required by glium
+    IncompatibleOpenGl(IncompatibleOpenGl),
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Creation(err) => write!(f, "{err}"),
+            Self::IncompatibleOpenGl(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl error::Error for HeadlessError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Creation(err) => Some(err),
+            Self::IncompatibleOpenGl(err) => Some(err),
+        }
+    }
+}
+
+impl From<CreationError> for HeadlessError {
+    fn from(err: CreationError) -> Self {
+        Self::Creation(err)
+    }
+}
+
+impl From<IncompatibleOpenGl> for HeadlessError {
+    fn from(err: IncompatibleOpenGl) -> Self {
+        Self::IncompatibleOpenGl(err)
+    }
+}
+
+/// An OpenGL context without a window, e.g. for rendering in CI.
+///
+/// Such a context may lack a default frame buffer; frames should be rendered into a [`crate::capture::Offscreen`].
+pub struct Headless {
+    renderer: HeadlessRenderer,
+    /// keeps the connection to the display server alive if the context has been created through it
+    _event_loop: Option<EventLoop<()>>,
+}
+
+impl Deref for Headless {
+    type Target = HeadlessRenderer;
+
+    fn deref(&self) -> &HeadlessRenderer {
+        &self.renderer
+    }
+}
+
+impl Facade for Headless {
+    fn get_context(&self) -> &Rc<Context> {
+        self.renderer.get_context()
+    }
+}
+
+/// Creates an OpenGL context matching the engine's requirements without opening a window.
+///
+/// On Linux and the BSDs an `OSMesa` context is tried first, which doesn't need any display server; otherwise a hidden
+/// context of the display server is used (e.g. EGL with Mesa's llvmpipe).
+///
+/// # Errors
+///
+/// Fails if no compatible OpenGL context can be created.
+pub fn create_headless(settings: &Settings) -> Result<Headless, HeadlessError> {
+    let size = PhysicalSize::new(settings.window.width, settings.window.height);
+    let context_builder = ContextBuilder::new()
+            .with_gl(GL_REQUEST)
+            .with_gl_profile(GL_PROFILE)
+            .with_gl_robustness(GL_ROBUSTNESS)
+            .with_depth_buffer(settings.graphics.depth_buffer_bits)
+            ;
+
+    let headless = build_headless(context_builder, size)?;
+    dump_details(&headless);
+    Ok(headless)
+}
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+fn build_headless(context_builder: ContextBuilder<'_, NotCurrent>, size: PhysicalSize<u32>) -> Result<Headless, HeadlessError> {
+    use glium::glutin::platform::unix::{EventLoopExtUnix, HeadlessContextExt};
+
+    let osmesa_error = match context_builder.clone().build_osmesa(size) {
+        Ok(context) => return Ok(Headless { renderer: HeadlessRenderer::new(context)?, _event_loop: None }),
+        Err(err) => err,
+    };
+    debug!("could not create OSMesa context: {osmesa_error}");
+
+    // creating an event loop without a display server would panic
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Err(osmesa_error.into());
+    }
+    let event_loop = EventLoop::new_any_thread();
+    let context = context_builder.build_headless(&event_loop, size)?;
+    Ok(Headless { renderer: HeadlessRenderer::new(context)?, _event_loop: Some(event_loop) })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd")))]
+fn build_headless(context_builder: ContextBuilder<'_, NotCurrent>, size: PhysicalSize<u32>) -> Result<Headless, HeadlessError> {
+    let event_loop = EventLoop::new();
+    let context = context_builder.build_headless(&event_loop, size)?;
+    Ok(Headless { renderer: HeadlessRenderer::new(context)?, _event_loop: Some(event_loop) })
+}
+
+/// Returns a human readable description of the capabilities of the given OpenGL context; one line per property.
+#[must_use]
+pub fn details(display: &Context) -> String {
+    let (max_viewport_width, max_viewport_height) = display.get_max_viewport_dimensions();
+    let (framebuffer_width, framebuffer_height) = display.get_framebuffer_dimensions();
+    let free_memory_str = display.get_free_video_memory().map_or_else(|| "(unknown)".to_string(), |memory| memory.to_string());
+    let max_anisotropy_str = display.get_max_anisotropy_support().map_or_else(|| "(unknown)".to_string(), |anisotropy| anisotropy.to_string());
+    let opengl_profile_str = display.get_opengl_profile().map_or_else(|| "(unknown)".to_string(), |profile| format!("{profile:?}"));
+
+    let lines = [
+        format!("max viewport dimensions: {max_viewport_width} × {max_viewport_height}"),
+        format!("framebuffer dimensions : {framebuffer_width} × {framebuffer_height}"),
+        format!("free video memory      : {free_memory_str}"),
+        format!("max anisotropy support : {max_anisotropy_str}"),
+        format!("opengl profile         : {opengl_profile_str}"),
+        format!("opengl renderer string : {}", display.get_opengl_renderer_string()),
+        format!("opengl vendor string   : {}", display.get_opengl_vendor_string()),
+        format!("opengl version         : {:?}", display.get_opengl_version()),
+        format!("opengl version string  : {}", display.get_opengl_version_string()),
+        format!("release behavior       : {:?}", display.get_release_behavior()),
+        format!("supported glsl version : {:?}", display.get_supported_glsl_version()),
+        format!("context loss possible  : {}", if display.is_context_loss_possible() { "yes" } else { "no" }),
+        format!("debug                  : {}", if display.is_debug() { "yes" } else { "no" }),
+        format!("forward compatible     : {}", if display.is_forward_compatible() { "yes" } else { "no" }),
+        format!("robust                 : {}", if display.is_robust() { "yes" } else { "no" }),
+    ];
+    lines.join("\n")
+}
+
+/// Writes the capabilities of the given OpenGL context into the debug log.
+pub fn dump_details(display: &Context) {
+    for line in details(display).lines() {
+        debug!("{line}");
+    }
+}
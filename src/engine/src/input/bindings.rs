@@ -0,0 +1,333 @@
+use crate::input::keyboard::KeyFunction;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+use glium::glutin::event::{MouseButton, VirtualKeyCode};
+use log::{debug, warn};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{self, value::StrDeserializer};
+
+/// Name of the key bindings file within the configuration directory
+pub const BINDINGS_FILE_NAME: &str = "bindings.toml";
+
+/// Key bindings as stored in a file: the name of each function along with the names of its keys.
+type BindingsFile = BTreeMap<String, Vec<Trigger>>;
+
+/// A key or mouse button that can be bound to a function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+impl Trigger {
+
+    /// The name used for this trigger in configuration files; keys are named like their `VirtualKeyCode`, mouse buttons
+    /// are called `MouseLeft`, `MouseRight`, `MouseMiddle`, `Mouse4`, `Mouse5`, etc.
+    #[must_use]
+    pub fn name(self) -> String {
+        match self {
+            Trigger::Key(key) => format!("{key:?}"),
+            Trigger::Mouse(MouseButton::Left) => "MouseLeft".to_string(),
+            Trigger::Mouse(MouseButton::Right) => "MouseRight".to_string(),
+            Trigger::Mouse(MouseButton::Middle) => "MouseMiddle".to_string(),
+            Trigger::Mouse(MouseButton::Other(index)) => format!("Mouse{index}"),
+        }
+    }
+
+    /// Looks up a trigger by the name used in configuration files.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "MouseLeft" => Some(Trigger::Mouse(MouseButton::Left)),
+            "MouseRight" => Some(Trigger::Mouse(MouseButton::Right)),
+            "MouseMiddle" => Some(Trigger::Mouse(MouseButton::Middle)),
+            _ => if let Some(index) = name.strip_prefix("Mouse").and_then(|index| index.parse().ok()) {
+                Some(Trigger::Mouse(MouseButton::Other(index)))
+            } else {
+                VirtualKeyCode::deserialize(StrDeserializer::<de::value::Error>::new(name)).ok().map(Trigger::Key)
+            },
+        }
+    }
+
+}
+
+impl Serialize for Trigger {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Trigger {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Trigger::from_name(&name).ok_or_else(|| de::Error::custom(format!("unknown key or mouse button `{name}`")))
+    }
+}
+
+/// Everything that went wrong while reading or writing key bindings.
+#[derive(Debug)]
+pub enum BindingsError {
+    /// The bindings file could not be read or written
+    Io(io::Error),
+    /// The bindings file is not valid TOML or contains unknown key names
+    Parse(toml::de::Error),
+    /// The bindings could not be converted into TOML
+    Serialize(toml::ser::Error),
+    /// There's no function of the given name
+    UnknownFunction(String),
+    /// The same key has been bound to two different functions
+    Conflict { key: Trigger, first: KeyFunction, second: KeyFunction },
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Parse(err) => write!(f, "{err}"),
+            Self::Serialize(err) => write!(f, "{err}"),
+            Self::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            Self::Conflict { key, first, second } =>
+                write!(f, "`{}` is bound to both `{}` and `{}`", key.name(), first.name(), second.name()),
+        }
+    }
+}
+
+impl error::Error for BindingsError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Serialize(err) => Some(err),
+            Self::UnknownFunction(_) | Self::Conflict { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for BindingsError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for BindingsError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<toml::ser::Error> for BindingsError {
+    fn from(err: toml::ser::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+/// Maps keys and mouse buttons to the functions they trigger.
+///
+/// A function may be bound to any number of keys but every key triggers at most one function.
+pub struct Bindings {
+    map: HashMap<Trigger, KeyFunction>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut map = HashMap::new();
+
+        // usual race controls
+        map.insert(Trigger::Key(VirtualKeyCode::W), KeyFunction::Accelerate);
+        map.insert(Trigger::Key(VirtualKeyCode::S), KeyFunction::Decelerate);
+        map.insert(Trigger::Key(VirtualKeyCode::A), KeyFunction::YawLeft);
+        map.insert(Trigger::Key(VirtualKeyCode::D), KeyFunction::YawRight);
+
+        // Zero-G controls
+        map.insert(Trigger::Key(VirtualKeyCode::Up), KeyFunction::PitchDown);
+        map.insert(Trigger::Key(VirtualKeyCode::Down), KeyFunction::PitchUp);
+        map.insert(Trigger::Key(VirtualKeyCode::Left), KeyFunction::StrafeLeft);
+        map.insert(Trigger::Key(VirtualKeyCode::Right), KeyFunction::StrafeRight);
+        map.insert(Trigger::Key(VirtualKeyCode::Q), KeyFunction::RollLeft);
+        map.insert(Trigger::Key(VirtualKeyCode::E), KeyFunction::RollRight);
+
+        // flight controls
+        map.insert(Trigger::Key(VirtualKeyCode::PageUp), KeyFunction::Ascend);
+        map.insert(Trigger::Key(VirtualKeyCode::PageDown), KeyFunction::Descend);
+
+        // cameras
+        map.insert(Trigger::Key(VirtualKeyCode::C), KeyFunction::NextCamera);
+        map.insert(Trigger::Key(VirtualKeyCode::B), KeyFunction::LookBehind);
+
+        Self { map }
+    }
+}
+
+impl Bindings {
+
+    /// Returns the function bound to `key`, if any.
+    #[must_use]
+    pub fn map(&self, key: VirtualKeyCode) -> Option<KeyFunction> {
+        self.map.get(&Trigger::Key(key)).copied()
+    }
+
+    /// Returns the function bound to the mouse `button`, if any.
+    #[must_use]
+    pub fn map_mouse(&self, button: MouseButton) -> Option<KeyFunction> {
+        self.map.get(&Trigger::Mouse(button)).copied()
+    }
+
+    /// Returns all keys and mouse buttons bound to `function` sorted by their names.
+    #[must_use]
+    pub fn keys(&self, function: KeyFunction) -> Vec<Trigger> {
+        let mut keys: Vec<_> = self.map.iter()
+                .filter(|(_, &bound)| bound == function)
+                .map(|(&key, _)| key)
+                .collect();
+        keys.sort_by_key(|key| key.name());
+        keys
+    }
+
+    /// Replaces all keys bound to `function` by `keys`.
+    ///
+    /// Keys that have been bound to other functions before are taken away from them; these former bindings are
+    /// returned.
+    pub fn bind(&mut self, function: KeyFunction, keys: &[Trigger]) -> Vec<(Trigger, KeyFunction)> {
+        self.map.retain(|_, bound| *bound != function);
+        keys.iter()
+                .filter_map(|&key| self.map.insert(key, function).map(|displaced| (key, displaced)))
+                .collect()
+    }
+
+    /// Applies the bindings given in TOML format on top of the current ones.
+    ///
+    /// Every function mentioned in `toml` loses its previous keys; all other functions keep theirs unless one of
+    /// their keys is being reused.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `toml` cannot be parsed, names unknown functions or binds a key to more than one function.
+    pub fn apply_toml(&mut self, toml: &str) -> Result<(), BindingsError> {
+        let file: BindingsFile = toml::from_str(toml)?;
+
+        // resolve all names and look for conflicts before changing anything
+        let mut overrides = Vec::with_capacity(file.len());
+        let mut seen = HashMap::new();
+        for (name, keys) in file {
+            let function = KeyFunction::from_name(&name).ok_or(BindingsError::UnknownFunction(name))?;
+            for &key in &keys {
+                if let Some(&first) = seen.get(&key) {
+                    if first != function {
+                        return Err(BindingsError::Conflict { key, first, second: function });
+                    }
+                }
+                seen.insert(key, function);
+            }
+            overrides.push((function, keys));
+        }
+
+        for (function, keys) in overrides {
+            for (key, displaced) in self.bind(function, &keys) {
+                if displaced != function {
+                    warn!("`{}` has been rebound from `{}` to `{}`", key.name(), displaced.name(), function.name());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the bindings in TOML format.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the bindings cannot be serialized.
+    pub fn to_toml(&self) -> Result<String, BindingsError> {
+        let file: BindingsFile = KeyFunction::ALL.iter()
+                .map(|&function| (function.name().to_string(), self.keys(function)))
+                .collect();
+        Ok(toml::to_string(&file)?)
+    }
+
+    /// Reads the default bindings and applies the user's bindings from the TOML file at `path` if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file exists but cannot be read, cannot be parsed or contains conflicting bindings.
+    pub fn load_or_default(path: &Path) -> Result<Self, BindingsError> {
+        let mut bindings = Self::default();
+        match fs::read_to_string(path) {
+            Ok(toml) => {
+                debug!("load key bindings from {}", path.display());
+                bindings.apply_toml(&toml)?;
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("no key bindings file found; using defaults");
+            },
+            Err(err) => return Err(err.into()),
+        }
+        Ok(bindings)
+    }
+
+    /// Writes the bindings into the TOML file at `path`, creating the parent directories if required.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the bindings cannot be serialized or the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), BindingsError> {
+        debug!("save key bindings to {}", path.display());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_bindings_replace_defaults() {
+        let mut bindings = Bindings::default();
+        bindings.apply_toml("accelerate = [\"Z\", \"Up\"]\nyaw_left = [\"Q\"]\nroll_right = [\"Mouse4\"]\n").unwrap();
+
+        assert_eq!(bindings.map(VirtualKeyCode::Z), Some(KeyFunction::Accelerate));
+        assert_eq!(bindings.map(VirtualKeyCode::Up), Some(KeyFunction::Accelerate));
+        assert_eq!(bindings.map(VirtualKeyCode::W), None);
+        assert_eq!(bindings.map(VirtualKeyCode::Q), Some(KeyFunction::YawLeft));
+        assert_eq!(bindings.map_mouse(MouseButton::Other(4)), Some(KeyFunction::RollRight));
+        // lost its only key to `yaw_left`
+        assert!(bindings.keys(KeyFunction::RollLeft).is_empty());
+        // untouched
+        assert_eq!(bindings.keys(KeyFunction::Decelerate), vec![Trigger::Key(VirtualKeyCode::S)]);
+    }
+
+    #[test]
+    fn conflicts_are_reported() {
+        let mut bindings = Bindings::default();
+        match bindings.apply_toml("accelerate = [\"X\"]\ndecelerate = [\"X\"]\n") {
+            Err(BindingsError::Conflict { key, .. }) => assert_eq!(key, Trigger::Key(VirtualKeyCode::X)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // nothing has been applied
+        assert_eq!(bindings.map(VirtualKeyCode::W), Some(KeyFunction::Accelerate));
+    }
+
+    #[test]
+    fn unknown_functions_are_reported() {
+        assert!(matches!(Bindings::default().apply_toml("jump = [\"Space\"]\n"), Err(BindingsError::UnknownFunction(_))));
+    }
+
+    #[test]
+    fn unknown_keys_are_reported() {
+        assert!(matches!(Bindings::default().apply_toml("accelerate = [\"NoSuchKey\"]\n"), Err(BindingsError::Parse(_))));
+    }
+
+    #[test]
+    fn round_trip() {
+        let bindings = Bindings::default();
+        let mut loaded = Bindings { map: HashMap::new() };
+        loaded.apply_toml(&bindings.to_toml().unwrap()).unwrap();
+        assert_eq!(loaded.map, bindings.map);
+    }
+}
@@ -0,0 +1,382 @@
+use std::convert::TryFrom;
+
+use gilrs::{Axis, Button, EventType, Gamepad, GamepadId, Gilrs};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::input::Axes;
+use crate::viewport::MAX_PLAYERS;
+
+/// An analog or digital input element of a gamepad.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamepadInput {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+    LeftBumper,
+    RightBumper,
+    NorthButton,
+    EastButton,
+    SouthButton,
+    WestButton,
+    LeftStickButton,
+    RightStickButton,
+}
+
+impl GamepadInput {
+
+    /// Reads the current value of this input; sticks yield `-1.0..=1.0`, triggers and buttons `0.0..=1.0`.
+    fn value(self, gamepad: &Gamepad<'_>) -> f32 {
+        match self {
+            GamepadInput::LeftStickX => gamepad.value(Axis::LeftStickX),
+            GamepadInput::LeftStickY => gamepad.value(Axis::LeftStickY),
+            GamepadInput::RightStickX => gamepad.value(Axis::RightStickX),
+            GamepadInput::RightStickY => gamepad.value(Axis::RightStickY),
+            input => input.button().map_or(0.0, |button| button_value(gamepad, button)),
+        }
+    }
+
+    /// The button behind this input unless it is a stick axis.
+    fn button(self) -> Option<Button> {
+        match self {
+            GamepadInput::LeftStickX | GamepadInput::LeftStickY | GamepadInput::RightStickX | GamepadInput::RightStickY => None,
+            GamepadInput::LeftTrigger => Some(Button::LeftTrigger2),
+            GamepadInput::RightTrigger => Some(Button::RightTrigger2),
+            GamepadInput::LeftBumper => Some(Button::LeftTrigger),
+            GamepadInput::RightBumper => Some(Button::RightTrigger),
+            GamepadInput::NorthButton => Some(Button::North),
+            GamepadInput::EastButton => Some(Button::East),
+            GamepadInput::SouthButton => Some(Button::South),
+            GamepadInput::WestButton => Some(Button::West),
+            GamepadInput::LeftStickButton => Some(Button::LeftThumb),
+            GamepadInput::RightStickButton => Some(Button::RightThumb),
+        }
+    }
+
+}
+
+fn button_value(gamepad: &Gamepad<'_>, button: Button) -> f32 {
+    gamepad.button_data(button).map_or(0.0, gilrs::ev::state::ButtonData::value)
+}
+
+/// Puts `id` into the first empty slot unless it already has one; returns whether it has a slot now.
+fn assign_slot<T: Copy + PartialEq>(slots: &mut [Option<T>], id: T) -> bool {
+    if slots.contains(&Some(id)) {
+        return true;
+    }
+    slots.iter_mut().find(|slot| slot.is_none()).map(|slot| *slot = Some(id)).is_some()
+}
+
+/// Leaves the slot of `id` empty.
+fn clear_slot<T: Copy + PartialEq>(slots: &mut [Option<T>], id: T) {
+    for slot in slots.iter_mut().filter(|slot| **slot == Some(id)) {
+        *slot = None;
+    }
+}
+
+fn count(presses: usize) -> u32 {
+    u32::try_from(presses).unwrap_or(u32::MAX)
+}
+
+/// Describes how the inputs of a gamepad drive a single control axis.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AxisMapping {
+    /// input moving the axis into the positive direction
+    pub positive: Option<GamepadInput>,
+    /// input moving the axis into the negative direction; useful to combine two triggers into one axis
+    pub negative: Option<GamepadInput>,
+    /// fraction of the input range around the rest position that will be ignored
+    pub deadzone: f32,
+    /// whether to flip the direction of the axis
+    pub invert: bool,
+    /// factor applied to the input after removing the deadzone
+    pub sensitivity: f32,
+}
+
+impl Default for AxisMapping {
+    fn default() -> Self {
+        Self {
+            positive: None,
+            negative: None,
+            deadzone: 0.1,
+            invert: false,
+            sensitivity: 1.0,
+        }
+    }
+}
+
+impl AxisMapping {
+
+    fn new(positive: GamepadInput, negative: Option<GamepadInput>, invert: bool) -> Self {
+        Self {
+            positive: Some(positive),
+            negative,
+            invert,
+            ..Self::default()
+        }
+    }
+
+    /// Converts the raw values of the positive and negative input into the value of the axis.
+    #[must_use]
+    pub fn apply(&self, positive: f32, negative: f32) -> f32 {
+        let raw = positive - negative;
+
+        // rescale the remaining range so the output still starts at zero and reaches one at full deflection
+        let magnitude = raw.abs();
+        let value = if magnitude <= self.deadzone {
+            0.0
+        } else {
+            raw.signum() * (magnitude - self.deadzone) / (1.0 - self.deadzone)
+        };
+
+        let value = value * self.sensitivity;
+        let value = if self.invert { -value } else { value };
+        value.clamp(-1.0, 1.0)
+    }
+
+    fn read(&self, gamepad: &Gamepad<'_>) -> f32 {
+        let positive = self.positive.map_or(0.0, |input| input.value(gamepad));
+        let negative = self.negative.map_or(0.0, |input| input.value(gamepad));
+        self.apply(positive, negative)
+    }
+
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GamepadSettings {
+    /// whether to look for gamepads at all
+    pub enabled: bool,
+    /// input switching to the next camera
+    pub next_camera: Option<GamepadInput>,
+    /// input to hold for looking behind
+    pub look_behind: Option<GamepadInput>,
+    pub thrust: AxisMapping,
+    pub strafe: AxisMapping,
+    pub ascend: AxisMapping,
+    pub yaw: AxisMapping,
+    pub pitch: AxisMapping,
+    pub roll: AxisMapping,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            next_camera: Some(GamepadInput::NorthButton),
+            look_behind: Some(GamepadInput::EastButton),
+            thrust: AxisMapping::new(GamepadInput::RightTrigger, Some(GamepadInput::LeftTrigger), false),
+            strafe: AxisMapping::new(GamepadInput::RightStickX, None, false),
+            ascend: AxisMapping::new(GamepadInput::RightStickY, None, false),
+            // yaw and pitch are positive when turning left and pulling up respectively
+            yaw: AxisMapping::new(GamepadInput::LeftStickX, None, true),
+            pitch: AxisMapping::new(GamepadInput::LeftStickY, None, true),
+            roll: AxisMapping::new(GamepadInput::LeftBumper, Some(GamepadInput::RightBumper), false),
+        }
+    }
+}
+
+impl GamepadSettings {
+
+    /// Returns the first mapping having an invalid value along with its name.
+    pub(crate) fn invalid_mapping(&self) -> Option<&'static str> {
+        let mappings = [
+            ("thrust", &self.thrust),
+            ("strafe", &self.strafe),
+            ("ascend", &self.ascend),
+            ("yaw", &self.yaw),
+            ("pitch", &self.pitch),
+            ("roll", &self.roll),
+        ];
+        mappings.iter()
+                .find(|(_, mapping)| !(0.0..1.0).contains(&mapping.deadzone) || !mapping.sensitivity.is_finite())
+                .map(|(name, _)| *name)
+    }
+
+}
+
+/// Translates the state of the most recently used gamepad, or of a gamepad assigned to a player, into analog values
+/// of the control axes and camera commands.
+///
+/// Gamepads may be connected and disconnected at any time. Each connected gamepad takes the first empty one of
+/// [`MAX_PLAYERS`] slots, which it leaves empty when disconnected, so the gamepads of the other players stay in
+/// their slots.
+pub struct Control {
+    gilrs: Gilrs,
+    settings: GamepadSettings,
+    /// the gamepad that produced the most recent input
+    active: Option<GamepadId>,
+    /// the gamepads assigned to the players
+    slots: [Option<GamepadId>; MAX_PLAYERS],
+    /// the gamepads that requested the next camera during the last update, once per press
+    next_camera_presses: Vec<GamepadId>,
+}
+
+impl Control {
+
+    /// Starts listening for gamepads; returns `None` if gamepads are disabled or not supported on this system.
+    #[must_use]
+    pub fn new(settings: GamepadSettings) -> Option<Self> {
+        if !settings.enabled {
+            debug!("gamepads are disabled");
+            return None;
+        }
+
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(err) => {
+                warn!("gamepads are not available: {err}");
+                return None;
+            }
+        };
+
+        let mut control = Self { gilrs, settings, active: None, slots: [None; MAX_PLAYERS], next_camera_presses: Vec::new() };
+        let connected: Vec<GamepadId> = control.gilrs.gamepads().map(|(id, gamepad)| {
+            info!("found gamepad `{}`", gamepad.name());
+            id
+        }).collect();
+        for id in connected {
+            control.active.get_or_insert(id);
+            control.take_slot(id);
+        }
+
+        Some(control)
+    }
+
+    /// Processes all pending gamepad events; has to be called once per simulation tick.
+    pub fn update(&mut self) {
+        self.next_camera_presses.clear();
+        let next_camera = self.settings.next_camera.and_then(GamepadInput::button);
+
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::Connected => {
+                    info!("gamepad `{}` connected", self.gilrs.gamepad(event.id).name());
+                    self.active.get_or_insert(event.id);
+                    self.take_slot(event.id);
+                },
+                EventType::Disconnected => {
+                    info!("gamepad `{}` disconnected", self.gilrs.gamepad(event.id).name());
+                    clear_slot(&mut self.slots, event.id);
+                    if self.active == Some(event.id) {
+                        self.active = self.gilrs.gamepads().map(|(id, _)| id).find(|&id| id != event.id);
+                    }
+                },
+                EventType::ButtonPressed(button, _) => {
+                    self.active = Some(event.id);
+                    if Some(button) == next_camera {
+                        self.next_camera_presses.push(event.id);
+                    }
+                },
+                EventType::ButtonChanged(..) | EventType::AxisChanged(..) => {
+                    self.active = Some(event.id);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn take_slot(&mut self, id: GamepadId) {
+        if !assign_slot(&mut self.slots, id) {
+            debug!("no slot left for gamepad `{}`", self.gilrs.gamepad(id).name());
+        }
+    }
+
+    /// Returns the axes as requested by the active gamepad.
+    #[must_use]
+    pub fn axes(&self) -> Axes {
+        self.active.map_or_else(Axes::default, |id| self.read(id))
+    }
+
+    /// Returns the axes as requested by the gamepad in slot `index`, counting from 0.
+    #[must_use]
+    pub fn player_axes(&self, index: usize) -> Axes {
+        self.slot(index).map_or_else(Axes::default, |id| self.read(id))
+    }
+
+    /// Returns how often the next camera has been requested on any gamepad during the last update.
+    #[must_use]
+    pub fn next_camera(&self) -> u32 {
+        count(self.next_camera_presses.len())
+    }
+
+    /// Returns how often the next camera has been requested on the gamepad in slot `index` during the last update.
+    #[must_use]
+    pub fn player_next_camera(&self, index: usize) -> u32 {
+        self.slot(index).map_or(0, |id| count(self.next_camera_presses.iter().filter(|&&pressed| pressed == id).count()))
+    }
+
+    /// Whether the input for looking behind is being held down on the active gamepad.
+    #[must_use]
+    pub fn look_behind(&self) -> bool {
+        self.active.is_some_and(|id| self.holds_look_behind(id))
+    }
+
+    /// Whether the input for looking behind is being held down on the gamepad in slot `index`.
+    #[must_use]
+    pub fn player_look_behind(&self, index: usize) -> bool {
+        self.slot(index).is_some_and(|id| self.holds_look_behind(id))
+    }
+
+    fn slot(&self, index: usize) -> Option<GamepadId> {
+        self.slots.get(index).copied().flatten()
+    }
+
+    fn holds_look_behind(&self, id: GamepadId) -> bool {
+        self.settings.look_behind.is_some_and(|input| input.value(&self.gilrs.gamepad(id)) > 0.5)
+    }
+
+    fn read(&self, id: GamepadId) -> Axes {
+        let gamepad = self.gilrs.gamepad(id);
+
+        Axes {
+            thrust: self.settings.thrust.read(&gamepad),
+            strafe: self.settings.strafe.read(&gamepad),
+            ascend: self.settings.ascend.read(&gamepad),
+            yaw: self.settings.yaw.read(&gamepad),
+            pitch: self.settings.pitch.read(&gamepad),
+            roll: self.settings.roll.read(&gamepad),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_is_removed_smoothly() {
+        let mapping = AxisMapping { deadzone: 0.2, ..AxisMapping::default() };
+        assert!(mapping.apply(0.15, 0.0).abs() < f32::EPSILON);
+        assert!((mapping.apply(0.6, 0.0) - 0.5).abs() < 1e-6);
+        assert!((mapping.apply(0.0, 1.0) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gamepads_keep_their_slots() {
+        let mut slots = [None; 3];
+        for id in 1..=3 {
+            assert!(assign_slot(&mut slots, id));
+        }
+        assert!(!assign_slot(&mut slots, 4));
+        assert!(assign_slot(&mut slots, 2));
+
+        clear_slot(&mut slots, 2);
+        assert_eq!(slots, [Some(1), None, Some(3)]);
+        assert!(assign_slot(&mut slots, 4));
+        assert_eq!(slots, [Some(1), Some(4), Some(3)]);
+    }
+
+    #[test]
+    fn sensitivity_and_inversion() {
+        let mapping = AxisMapping { deadzone: 0.0, invert: true, sensitivity: 2.0, ..AxisMapping::default() };
+        assert!((mapping.apply(0.25, 0.0) + 0.5).abs() < 1e-6);
+        assert!((mapping.apply(1.0, 0.0) + 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,197 @@
+use glium::glutin::event::{ElementState, KeyboardInput, MouseButton};
+
+use crate::input::Axes;
+use crate::input::bindings::Bindings;
+
+/// A function that can be triggered by pressing a key or mouse button.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyFunction {
+    Accelerate,
+    Decelerate,
+    StrafeRight,
+    StrafeLeft,
+    Ascend,
+    Descend,
+    YawLeft,
+    YawRight,
+    PitchUp,
+    PitchDown,
+    RollLeft,
+    RollRight,
+    NextCamera,
+    LookBehind,
+}
+
+impl KeyFunction {
+
+    /// All functions in the order they are presented to the user.
+    pub const ALL: [KeyFunction; 14] = [
+        KeyFunction::Accelerate,
+        KeyFunction::Decelerate,
+        KeyFunction::StrafeRight,
+        KeyFunction::StrafeLeft,
+        KeyFunction::Ascend,
+        KeyFunction::Descend,
+        KeyFunction::YawLeft,
+        KeyFunction::YawRight,
+        KeyFunction::PitchUp,
+        KeyFunction::PitchDown,
+        KeyFunction::RollLeft,
+        KeyFunction::RollRight,
+        KeyFunction::NextCamera,
+        KeyFunction::LookBehind,
+    ];
+
+    /// The name used for this function in configuration files.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyFunction::Accelerate => "accelerate",
+            KeyFunction::Decelerate => "decelerate",
+            KeyFunction::StrafeRight => "strafe_right",
+            KeyFunction::StrafeLeft => "strafe_left",
+            KeyFunction::Ascend => "ascend",
+            KeyFunction::Descend => "descend",
+            KeyFunction::YawLeft => "yaw_left",
+            KeyFunction::YawRight => "yaw_right",
+            KeyFunction::PitchUp => "pitch_up",
+            KeyFunction::PitchDown => "pitch_down",
+            KeyFunction::RollLeft => "roll_left",
+            KeyFunction::RollRight => "roll_right",
+            KeyFunction::NextCamera => "next_camera",
+            KeyFunction::LookBehind => "look_behind",
+        }
+    }
+
+    /// Looks up a function by the name used in configuration files.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|function| function.name() == name)
+    }
+
+}
+
+#[derive(Copy, Clone, Default)]
+struct Orientation{
+    positive: i8,
+    negative: i8,
+}
+
+impl Orientation {
+
+    pub fn set_positive(&mut self, positive: bool) {
+        self.positive = i8::from(positive);
+    }
+
+    pub fn set_negative(&mut self, negative: bool) {
+        self.negative = i8::from(negative);
+    }
+
+    pub fn value(self) -> i8 {
+        self.positive - self.negative
+    }
+
+}
+
+impl From<Orientation> for f32 {
+    fn from(orientation: Orientation) -> Self {
+        f32::from(orientation.value())
+    }
+}
+
+/// Translates keyboard and mouse button input into digital changes of the control axes and camera commands.
+#[derive(Default)]
+pub struct Control {
+    bindings: Bindings,
+
+    thrust: Orientation,
+    strafe: Orientation,
+    ascend: Orientation,
+
+    yaw: Orientation,
+    pitch: Orientation,
+    roll: Orientation,
+
+    next_camera_held: bool,
+    /// number of presses of [`KeyFunction::NextCamera`] not taken yet
+    next_camera_presses: u32,
+    look_behind: bool,
+}
+
+impl Control {
+
+    /// Creates a keyboard input source using the given key bindings.
+    #[must_use]
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            ..Self::default()
+        }
+    }
+
+    /// Updates the state of the axes according to the pressed or released key.
+    pub fn process_keyboard_input(&mut self, input: KeyboardInput) {
+        if let Some(key) = input.virtual_keycode {
+            if let Some(function) = self.bindings.map(key) {
+                self.trigger(function, input.state == ElementState::Pressed);
+            }
+        }
+    }
+
+    /// Updates the state of the axes according to the pressed or released mouse button.
+    pub fn process_mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        if let Some(function) = self.bindings.map_mouse(button) {
+            self.trigger(function, state == ElementState::Pressed);
+        }
+    }
+
+    fn trigger(&mut self, function: KeyFunction, pressed: bool) {
+        match function {
+            KeyFunction::NextCamera => {
+                // ignore the repetitions of a key being held down
+                if pressed && !self.next_camera_held {
+                    self.next_camera_presses += 1;
+                }
+                self.next_camera_held = pressed;
+            },
+            KeyFunction::LookBehind => self.look_behind = pressed,
+            KeyFunction::Accelerate => self.thrust.set_positive(pressed),
+            KeyFunction::Decelerate => self.thrust.set_negative(pressed),
+            KeyFunction::StrafeRight => self.strafe.set_positive(pressed),
+            KeyFunction::StrafeLeft => self.strafe.set_negative(pressed),
+            KeyFunction::Ascend => self.ascend.set_positive(pressed),
+            KeyFunction::Descend => self.ascend.set_negative(pressed),
+            KeyFunction::YawLeft => self.yaw.set_positive(pressed),
+            KeyFunction::YawRight => self.yaw.set_negative(pressed),
+            KeyFunction::PitchUp => self.pitch.set_positive(pressed),
+            KeyFunction::PitchDown => self.pitch.set_negative(pressed),
+            KeyFunction::RollLeft => self.roll.set_positive(pressed),
+            KeyFunction::RollRight => self.roll.set_negative(pressed),
+        }
+    }
+
+    /// Returns how often the next camera has been requested since the last call.
+    pub fn take_next_camera(&mut self) -> u32 {
+        std::mem::take(&mut self.next_camera_presses)
+    }
+
+    /// Whether the key for looking behind is being held down.
+    #[must_use]
+    pub fn look_behind(&self) -> bool {
+        self.look_behind
+    }
+
+    /// Returns the axes as requested by the currently pressed keys.
+    #[must_use]
+    pub fn axes(&self) -> Axes {
+        Axes {
+            thrust: self.thrust.into(),
+            strafe: self.strafe.into(),
+            ascend: self.ascend.into(),
+            yaw: self.yaw.into(),
+            pitch: self.pitch.into(),
+            roll: self.roll.into(),
+        }
+    }
+
+}
\ No newline at end of file
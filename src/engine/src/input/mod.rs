@@ -0,0 +1,308 @@
+use std::ops::Add;
+
+use serde::{Deserialize, Serialize};
+
+use response::{Easing, Response, ResponseSettings};
+
+pub mod bindings;
+pub mod gamepad;
+pub mod keyboard;
+pub mod mouse;
+pub mod replay;
+pub mod response;
+
+// #[derive(Copy, Clone)]
+// pub enum Action {
+//     Thrust(f32), // accelerate, move forward
+//     Sidestep(f32), // strafe right
+//     Ascend(f32), // move upwards
+
+//     Yaw(f32), // turn left/right
+//     Pitch(f32), // pitch up/down
+//     Roll(f32), // roll left/right
+// }
+
+/// Below this distance an exponentially eased value is considered to have reached its set value
+const EXPONENTIAL_SNAP: f32 = 1e-4;
+
+/// An analog value that follows its set value with a limited speed instead of jumping there immediately.
+///
+/// The way the value approaches its set value and how it is mapped onto the output is described by a [`Response`].
+pub struct GradientValue {
+    /// the shaped output value
+    pub value: f32,
+    /// the unshaped value moving towards the set value
+    current: f32,
+    set_value: f32,
+    response: Response,
+    /// value at the beginning of the current s-curve segment; a new segment starts whenever the set value changes
+    ramp_from: f32,
+    /// speed in units per second at the beginning of the current s-curve segment
+    ramp_velocity: f32,
+    /// seconds the current s-curve segment takes
+    ramp_duration: f32,
+    /// progress of the ramp at the beginning of the current s-curve segment
+    ramp_start: f32,
+    /// progress of the current s-curve ramp (`0.0..=1.0`)
+    ramp_progress: f32,
+    /// current speed along the s-curve in units per second
+    velocity: f32,
+}
+
+impl Default for GradientValue {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl GradientValue {
+
+    /// Creates a value at rest that changes by at most `speed` units per second.
+    #[must_use]
+    pub fn new(speed: f32) -> Self {
+        Self::with_response(Response::linear(speed))
+    }
+
+    /// Creates a value at rest with the given response characteristic.
+    #[must_use]
+    pub fn with_response(response: Response) -> Self {
+        Self {
+            value: 0.0,
+            current: 0.0,
+            set_value: 0.0,
+            response,
+            ramp_from: 0.0,
+            ramp_velocity: 0.0,
+            ramp_duration: 0.0,
+            ramp_start: 0.0,
+            ramp_progress: 1.0,
+            velocity: 0.0,
+        }
+    }
+
+    /// Sets the value to move towards; clamped to `-1.0..=1.0`.
+    pub fn set(&mut self, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        if value.to_bits() != self.set_value.to_bits() {
+            self.set_value = value;
+            self.ramp_from = self.current;
+            self.ramp_duration = (value - self.current).abs() / self.response.rate(self.current, value);
+            if self.ramp_progress < 1.0 {
+                // bend the rest of the ramp towards the new set value without slowing down
+                self.ramp_velocity = self.velocity;
+                self.ramp_start = self.ramp_progress;
+            } else {
+                self.ramp_velocity = 0.0;
+                self.ramp_start = 0.0;
+                self.ramp_progress = 0.0;
+            }
+        }
+    }
+
+    /// Moves the value towards its set value for a time span of `time` seconds.
+    pub fn update(&mut self, time: f32) {
+        let target = self.set_value;
+        let rate = self.response.rate(self.current, target);
+
+        self.current = match self.response.easing {
+            Easing::Linear => {
+                if self.current < target {
+                    // increase value to bring it closer to the set value
+                    (self.current + rate * time).min(target)
+                } else {
+                    // decrease value to bring it closer to the set value
+                    (self.current - rate * time).max(target)
+                }
+            },
+            Easing::Exponential => {
+                let current = self.current + (target - self.current) * (1.0 - (-rate * time).exp());
+                if (target - current).abs() < EXPONENTIAL_SNAP { target } else { current }
+            },
+            Easing::SCurve => {
+                let remaining = 1.0 - self.ramp_start;
+                self.ramp_progress = if self.ramp_duration > 0.0 {
+                    (self.ramp_progress + remaining * time / self.ramp_duration).min(1.0)
+                } else {
+                    1.0
+                };
+                if self.ramp_progress < 1.0 {
+                    let t = (self.ramp_progress - self.ramp_start) / remaining;
+                    let (value, slope) = hermite(self.ramp_from, self.ramp_velocity * self.ramp_duration, target, t);
+                    self.velocity = slope / self.ramp_duration;
+                    value
+                } else {
+                    self.velocity = 0.0;
+                    target
+                }
+            },
+        };
+
+        self.value = self.response.shape(self.current);
+    }
+
+}
+
+/// Evaluates the cubic curve from `from` with the slope `slope` to `to` with a slope of zero at `t` (`0.0..=1.0`);
+/// returns the value and the slope at that point.
+///
+/// Starting with a slope of zero, this is the classic smoothstep s-curve.
+fn hermite(from: f32, slope: f32, to: f32, t: f32) -> (f32, f32) {
+    let (t2, t3) = (t * t, t * t * t);
+    let value = from + slope * (t3 - 2.0 * t2 + t) + (to - from) * (3.0 * t2 - 2.0 * t3);
+    let derivative = slope * (3.0 * t2 - 4.0 * t + 1.0) + (to - from) * (6.0 * t - 6.0 * t2);
+    (value, derivative)
+}
+
+/// The set values of all control axes as requested by an input source.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Axes {
+    pub thrust: f32,
+    pub strafe: f32,
+    pub ascend: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+}
+
+/// Combines the requests of two input sources; the result may exceed the valid range of an axis.
+impl Add for Axes {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            thrust: self.thrust + other.thrust,
+            strafe: self.strafe + other.strafe,
+            ascend: self.ascend + other.ascend,
+            yaw: self.yaw + other.yaw,
+            pitch: self.pitch + other.pitch,
+            roll: self.roll + other.roll,
+        }
+    }
+}
+
+/// The devices steering a player when several players share the screen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputSource {
+    /// the keyboard along with the mouse
+    Keyboard,
+    /// the gamepad connected first
+    Gamepad1,
+    Gamepad2,
+    Gamepad3,
+    Gamepad4,
+}
+
+impl InputSource {
+
+    /// The slot of the gamepad (see [`gamepad::Control`]), if this is a gamepad.
+    #[must_use]
+    pub fn gamepad_index(self) -> Option<usize> {
+        match self {
+            Self::Keyboard => None,
+            Self::Gamepad1 => Some(0),
+            Self::Gamepad2 => Some(1),
+            Self::Gamepad3 => Some(2),
+            Self::Gamepad4 => Some(3),
+        }
+    }
+
+}
+
+/// The analog control axes of a ship (or camera), each in the range `-1.0..=1.0`.
+pub struct Control {
+    pub thrust: GradientValue,
+    pub strafe: GradientValue,
+    pub ascend: GradientValue,
+    pub yaw: GradientValue,
+    pub pitch: GradientValue,
+    pub roll: GradientValue,
+}
+
+impl Control {
+
+    /// Creates a set of control axes at rest, all of them sharing the same `gradient_speed`.
+    #[must_use]
+    pub fn new(gradient_speed: f32) -> Self {
+        Self {
+            thrust: GradientValue::new(gradient_speed),
+            strafe: GradientValue::new(gradient_speed),
+            ascend: GradientValue::new(gradient_speed),
+            yaw: GradientValue::new(gradient_speed),
+            pitch: GradientValue::new(gradient_speed),
+            roll: GradientValue::new(gradient_speed),
+        }
+    }
+
+    /// Creates a set of control axes at rest, each of them with its own response; rates not given in `responses`
+    /// default to `gradient_speed`.
+    #[must_use]
+    pub fn with_responses(gradient_speed: f32, responses: &ResponseSettings) -> Self {
+        Self {
+            thrust: GradientValue::with_response(responses.thrust.resolve(gradient_speed)),
+            strafe: GradientValue::with_response(responses.strafe.resolve(gradient_speed)),
+            ascend: GradientValue::with_response(responses.ascend.resolve(gradient_speed)),
+            yaw: GradientValue::with_response(responses.yaw.resolve(gradient_speed)),
+            pitch: GradientValue::with_response(responses.pitch.resolve(gradient_speed)),
+            roll: GradientValue::with_response(responses.roll.resolve(gradient_speed)),
+        }
+    }
+
+    /// Advances all axes by a time span of `time` seconds.
+    pub fn update(&mut self, time: f32) {
+        self.thrust.update(time);
+        self.strafe.update(time);
+        self.ascend.update(time);
+        self.yaw.update(time);
+        self.pitch.update(time);
+        self.roll.update(time);
+    }
+
+    /// Sets the set values of all axes at once; values are clamped to the valid range.
+    pub fn set_axes(&mut self, axes: Axes) {
+        self.set_thrust(axes.thrust);
+        self.set_strafe(axes.strafe);
+        self.set_ascend(axes.ascend);
+        self.set_yaw(axes.yaw);
+        self.set_pitch(axes.pitch);
+        self.set_roll(axes.roll);
+    }
+
+    /// Returns the set values all axes are heading to.
+    #[must_use]
+    pub fn set_values(&self) -> Axes {
+        Axes {
+            thrust: self.thrust.set_value,
+            strafe: self.strafe.set_value,
+            ascend: self.ascend.set_value,
+            yaw: self.yaw.set_value,
+            pitch: self.pitch.set_value,
+            roll: self.roll.set_value,
+        }
+    }
+
+    pub fn set_thrust(&mut self, value: f32) {
+        self.thrust.set(value);
+    }
+
+    pub fn set_strafe(&mut self, value: f32) {
+        self.strafe.set(value);
+    }
+
+    pub fn set_ascend(&mut self, value: f32) {
+        self.ascend.set(value);
+    }
+
+    pub fn set_yaw(&mut self, value: f32) {
+        self.yaw.set(value);
+    }
+
+    pub fn set_pitch(&mut self, value: f32) {
+        self.pitch.set(value);
+    }
+
+    pub fn set_roll(&mut self, value: f32) {
+        self.roll.set(value);
+    }
+
+}
\ No newline at end of file
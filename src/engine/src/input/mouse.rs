@@ -0,0 +1,118 @@
+use glium::glutin::window::Window;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::input::Axes;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MouseSettings {
+    /// whether mouse motion steers at all
+    pub enabled: bool,
+    /// change of the axis value per unit of mouse motion within a single tick
+    pub sensitivity: f32,
+    /// fraction of the previous value that is kept each tick; 0.0 disables smoothing
+    pub smoothing: f32,
+    /// whether moving the mouse to the right turns left
+    pub invert_x: bool,
+    /// whether moving the mouse forward pitches down
+    pub invert_y: bool,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sensitivity: 0.05,
+            smoothing: 0.5,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+/// Translates relative mouse motion into yaw and pitch while the cursor is grabbed.
+pub struct Control {
+    settings: MouseSettings,
+    grabbed: bool,
+    /// motion received since the most recent update
+    motion: (f64, f64),
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Control {
+
+    #[must_use]
+    pub fn new(settings: MouseSettings) -> Self {
+        Self {
+            settings,
+            grabbed: false,
+            motion: (0.0, 0.0),
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    /// Whether the cursor is currently grabbed by the window.
+    #[must_use]
+    pub fn is_grabbed(&self) -> bool {
+        self.grabbed
+    }
+
+    /// Confines and hides the cursor within `window` to steer with the mouse, or releases it again.
+    ///
+    /// Mouse motion is ignored while the cursor is released.
+    pub fn set_grabbed(&mut self, window: &Window, grabbed: bool) {
+        let grabbed = grabbed && self.settings.enabled;
+        if grabbed == self.grabbed {
+            return;
+        }
+
+        if let Err(err) = window.set_cursor_grab(grabbed) {
+            warn!("could not {} the cursor: {}", if grabbed { "grab" } else { "release" }, err);
+            return;
+        }
+        window.set_cursor_visible(!grabbed);
+        debug!("cursor {}", if grabbed { "grabbed" } else { "released" });
+
+        self.grabbed = grabbed;
+        self.motion = (0.0, 0.0);
+    }
+
+    /// Accumulates relative mouse motion as reported by `DeviceEvent::MouseMotion`.
+    pub fn process_motion(&mut self, delta: (f64, f64)) {
+        if self.grabbed {
+            self.motion.0 += delta.0;
+            self.motion.1 += delta.1;
+        }
+    }
+
+    /// Converts the motion accumulated since the previous call into axis values; has to be called once per tick.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn update(&mut self) {
+        let (dx, dy) = self.motion;
+        self.motion = (0.0, 0.0);
+
+        // moving the mouse to the right turns right and moving it forward (negative y) pulls up
+        let yaw = -dx as f32 * self.settings.sensitivity;
+        let pitch = -dy as f32 * self.settings.sensitivity;
+        let yaw = if self.settings.invert_x { -yaw } else { yaw };
+        let pitch = if self.settings.invert_y { -pitch } else { pitch };
+
+        let smoothing = self.settings.smoothing;
+        self.yaw = (self.yaw * smoothing + yaw * (1.0 - smoothing)).clamp(-1.0, 1.0);
+        self.pitch = (self.pitch * smoothing + pitch * (1.0 - smoothing)).clamp(-1.0, 1.0);
+    }
+
+    /// Returns the axes as requested by the recent mouse motion.
+    #[must_use]
+    pub fn axes(&self) -> Axes {
+        Axes {
+            yaw: self.yaw,
+            pitch: self.pitch,
+            ..Axes::default()
+        }
+    }
+
+}
@@ -0,0 +1,342 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use log::debug;
+
+use crate::input::Axes;
+
+/// Identifies a recording file
+const MAGIC: &[u8; 4] = b"RLSR";
+
+/// Version of the file format; to be increased with every incompatible change
+const VERSION: u8 = 2;
+
+/// The input of a player during a single simulation tick.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Tick {
+    /// set values of the control axes
+    pub axes: Axes,
+    /// how often the next camera has been requested
+    pub next_camera: u32,
+    /// whether the chase camera looks behind
+    pub look_behind: bool,
+}
+
+/// The input of a player for each simulation tick of a session.
+///
+/// Consecutive ticks with identical input are stored as a single run, so long periods of constant input don't take
+/// up any space worth mentioning.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    tick_rate: u32,
+    /// number of consecutive ticks along with their input
+    runs: Vec<(u32, Tick)>,
+}
+
+impl Recording {
+
+    /// Creates an empty recording of a simulation running at `tick_rate` ticks per second.
+    #[must_use]
+    pub fn new(tick_rate: u32) -> Self {
+        Self {
+            tick_rate,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Number of simulation ticks per second the recording has been made with.
+    #[must_use]
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    /// Total number of recorded ticks.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.runs.iter().map(|&(count, _)| u64::from(count)).sum()
+    }
+
+    /// Whether no tick has been recorded at all.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Appends the input of the next tick.
+    pub fn push(&mut self, tick: Tick) {
+        match self.runs.last_mut() {
+            // comparing the bits makes sure the replay reproduces exactly the same values
+            Some((count, last)) if *count < u32::MAX && same_bits(*last, tick) => *count += 1,
+            _ => self.runs.push((1, tick)),
+        }
+    }
+
+    /// Returns the input of all ticks in order.
+    pub fn ticks(&self) -> impl Iterator<Item = Tick> + '_ {
+        self.runs.iter().flat_map(|&(count, tick)| std::iter::repeat_n(tick, count as usize))
+    }
+
+    /// Writes the recording in its binary format.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `writer` fails.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&self.tick_rate.to_le_bytes())?;
+        for &(count, tick) in &self.runs {
+            writer.write_all(&count.to_le_bytes())?;
+            for value in &values(tick.axes) {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&tick.next_camera.to_le_bytes())?;
+            writer.write_all(&[u8::from(tick.look_behind)])?;
+        }
+        Ok(())
+    }
+
+    /// Reads a recording in its binary format.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `reader` fails or doesn't provide a valid recording.
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a recording"));
+        }
+        let mut version = [0; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported recording version {}", version[0])));
+        }
+        let tick_rate = read_u32(reader)?;
+        if tick_rate == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "recording has a tick rate of zero"));
+        }
+
+        let mut runs = Vec::new();
+        // the file may only end in between two runs
+        while let Some(count) = read_count(reader)? {
+            let mut values = [0.0; 6];
+            for value in &mut values {
+                *value = f32::from_bits(read_u32(reader).map_err(truncated)?);
+            }
+            let next_camera = read_u32(reader).map_err(truncated)?;
+            let mut look_behind = [0; 1];
+            reader.read_exact(&mut look_behind).map_err(truncated)?;
+            let look_behind = match look_behind[0] {
+                0 => false,
+                1 => true,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid look behind flag")),
+            };
+            runs.push((count, Tick { axes: axes(values), next_camera, look_behind }));
+        }
+
+        Ok(Self { tick_rate, runs })
+    }
+
+    /// Reads a recording from the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or doesn't contain a valid recording.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        debug!("load recording from {}", path.display());
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the recording into the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        debug!("save recording of {} ticks to {}", self.len(), path.display());
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+}
+
+fn values(axes: Axes) -> [f32; 6] {
+    [axes.thrust, axes.strafe, axes.ascend, axes.yaw, axes.pitch, axes.roll]
+}
+
+fn axes(values: [f32; 6]) -> Axes {
+    let [thrust, strafe, ascend, yaw, pitch, roll] = values;
+    Axes { thrust, strafe, ascend, yaw, pitch, roll }
+}
+
+fn same_bits(a: Tick, b: Tick) -> bool {
+    (a.next_camera, a.look_behind) == (b.next_camera, b.look_behind)
+            && values(a.axes).iter().zip(values(b.axes).iter()).all(|(a, b)| a.to_bits() == b.to_bits())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads the tick count starting the next run, or returns `None` at the end of the file.
+fn read_count<R: Read>(reader: &mut R) -> io::Result<Option<u32>> {
+    let mut bytes = [0; 4];
+    let mut filled = 0;
+    while filled < bytes.len() {
+        match reader.read(&mut bytes[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {},
+            Err(err) => return Err(err),
+        }
+    }
+    match filled {
+        0 => Ok(None),
+        4 => Ok(Some(u32::from_le_bytes(bytes))),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "recording is truncated")),
+    }
+}
+
+/// Reports a recording ending in the middle of a run as invalid rather than as a regular end of the file.
+fn truncated(err: io::Error) -> io::Error {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        io::Error::new(io::ErrorKind::InvalidData, "recording is truncated")
+    } else {
+        err
+    }
+}
+
+/// Feeds the ticks of a recording back one at a time.
+pub struct Player {
+    ticks: Box<dyn Iterator<Item = Tick>>,
+    tick_rate: u32,
+}
+
+impl Player {
+
+    #[must_use]
+    pub fn new(recording: Recording) -> Self {
+        let tick_rate = recording.tick_rate;
+        let ticks = recording.runs.into_iter().flat_map(|(count, tick)| std::iter::repeat_n(tick, count as usize));
+        Self {
+            ticks: Box::new(ticks),
+            tick_rate,
+        }
+    }
+
+    /// Number of simulation ticks per second the recording has been made with.
+    #[must_use]
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    /// Returns the input of the next tick or `None` after the last one.
+    pub fn next_tick(&mut self) -> Option<Tick> {
+        self.ticks.next()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::CameraState;
+    use crate::input::Control;
+    use cgmath::{InnerSpace, Quaternion, Vector3};
+
+    fn session() -> Recording {
+        let mut recording = Recording::new(60);
+        for tick in 0..600_u16 {
+            let phase = f32::from(tick) / 60.0;
+            recording.push(Tick {
+                axes: Axes {
+                    thrust: if tick < 300 { 1.0 } else { -0.5 },
+                    yaw: phase.sin(),
+                    pitch: if tick % 120 < 60 { 0.3 } else { 0.0 },
+                    ..Axes::default()
+                },
+                next_camera: u32::from(tick % 200 == 100),
+                look_behind: (400..450).contains(&tick),
+            });
+        }
+        recording
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn simulate(recording: &Recording) -> CameraState {
+        let time = 1.0 / recording.tick_rate() as f32;
+        let mut control = Control::new(1.0);
+        let mut camera = CameraState::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(0.0, 0.0, 0.0), 1.0);
+
+        let mut player = Player::new(recording.clone());
+        while let Some(tick) = player.next_tick() {
+            control.set_axes(tick.axes);
+            control.update(time);
+            camera.update_position(&control, time);
+        }
+        camera
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let recording = session();
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+
+        let loaded = Recording::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.len(), 600);
+        assert!(loaded.ticks().zip(recording.ticks()).all(|(a, b)| same_bits(a, b)));
+        assert_eq!(loaded.ticks().map(|tick| tick.next_camera).sum::<u32>(), 3);
+        assert_eq!(loaded.ticks().filter(|tick| tick.look_behind).count(), 50);
+    }
+
+    #[test]
+    fn constant_input_is_compact() {
+        let mut recording = Recording::new(60);
+        for _ in 0..1000 {
+            recording.push(Tick { axes: Axes { thrust: 1.0, ..Axes::default() }, ..Tick::default() });
+        }
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 4 + 1 + 4 + 4 + 6 * 4 + 4 + 1);
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        const RUN_SIZE: usize = 4 + 6 * 4 + 4 + 1;
+        let mut bytes = Vec::new();
+        session().write(&mut bytes).unwrap();
+
+        // cut off within the count of the last run, within its values and before its look behind flag
+        for cut in [2, 4 + 3 * 4, 4 + 6 * 4 + 4] {
+            let err = Recording::read(&mut &bytes[..bytes.len() - RUN_SIZE + cut]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        assert_eq!(Recording::read(&mut &bytes[..4 + 1 + 4]).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let recording = session();
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+        let loaded = Recording::read(&mut bytes.as_slice()).unwrap();
+
+        // where the camera ended up when the session was recorded
+        let position = Vector3::new(2.361_34, -1.862_668_6, -2.403_997_7);
+        let rotation = Quaternion::new(0.805_352_3, 0.241_248_25, 0.129_359_29, -0.525_807_26);
+
+        let camera = simulate(&loaded);
+        assert!((camera.position() - position).magnitude() < 1e-4);
+        assert!(camera.rotation().dot(rotation).abs() > 1.0 - 1e-6);
+
+        // replaying again gives exactly the same result
+        let again = simulate(&loaded);
+        assert_eq!(camera.position().map(f32::to_bits), again.position().map(f32::to_bits));
+    }
+}
@@ -0,0 +1,272 @@
+use serde::{Deserialize, Serialize};
+
+/// How a value approaches its set value over time.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// constant speed
+    Linear,
+    /// fast at first, slowing down when getting closer
+    Exponential,
+    /// slow start, fast middle, slow end; takes as long as `Linear`
+    SCurve,
+}
+
+/// How the internal value of an axis is mapped onto its output.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// output equals input
+    Linear,
+    /// `x^exponent` keeping the sign
+    Power,
+    /// blend between linear and cubic by `expo`; the classic RC model setting
+    Expo,
+}
+
+/// The resolved response characteristic of a single axis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    /// units per second while moving away from the rest position
+    pub attack: f32,
+    /// units per second while moving towards the rest position
+    pub release: f32,
+    pub easing: Easing,
+    pub curve: Curve,
+    /// exponent of the `Power` curve
+    pub exponent: f32,
+    /// amount of the cubic part of the `Expo` curve (`0.0..=1.0`)
+    pub expo: f32,
+    /// output range around the rest position that will be suppressed
+    pub deadzone: f32,
+}
+
+impl Response {
+
+    /// A response moving with `speed` units per second in both directions without any shaping.
+    #[must_use]
+    pub fn linear(speed: f32) -> Self {
+        Self {
+            attack: speed,
+            release: speed,
+            easing: Easing::Linear,
+            curve: Curve::Linear,
+            exponent: 1.0,
+            expo: 0.0,
+            deadzone: 0.0,
+        }
+    }
+
+    /// The speed to use for moving from `value` towards `target`.
+    #[must_use]
+    pub fn rate(&self, value: f32, target: f32) -> f32 {
+        let moving_away = target.abs() > value.abs() && target * value >= 0.0;
+        if moving_away { self.attack } else { self.release }
+    }
+
+    /// Applies the deadzone and output curve to `value`.
+    #[must_use]
+    pub fn shape(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+        // rescale the remaining range so the output still starts at zero and reaches one at full deflection
+        let magnitude = (magnitude - self.deadzone) / (1.0 - self.deadzone);
+
+        let magnitude = match self.curve {
+            Curve::Linear => magnitude,
+            Curve::Power => magnitude.powf(self.exponent),
+            Curve::Expo => self.expo * magnitude.powi(3) + (1.0 - self.expo) * magnitude,
+        };
+        magnitude.copysign(value)
+    }
+
+}
+
+/// Per-axis response settings as found in the settings file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AxisResponse {
+    /// units per second while moving away from the rest position; defaults to `input.gradient_speed`
+    pub attack: Option<f32>,
+    /// units per second while moving towards the rest position; defaults to `input.gradient_speed`
+    pub release: Option<f32>,
+    pub easing: Easing,
+    pub curve: Curve,
+    pub exponent: f32,
+    pub expo: f32,
+    pub deadzone: f32,
+}
+
+impl Default for AxisResponse {
+    fn default() -> Self {
+        Self {
+            attack: None,
+            release: None,
+            easing: Easing::Linear,
+            curve: Curve::Linear,
+            exponent: 2.0,
+            expo: 0.5,
+            deadzone: 0.0,
+        }
+    }
+}
+
+impl AxisResponse {
+
+    /// Fills in the missing rates with `default_speed`.
+    #[must_use]
+    pub fn resolve(&self, default_speed: f32) -> Response {
+        Response {
+            attack: self.attack.unwrap_or(default_speed),
+            release: self.release.unwrap_or(default_speed),
+            easing: self.easing,
+            curve: self.curve,
+            exponent: self.exponent,
+            expo: self.expo,
+            deadzone: self.deadzone,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.attack.into_iter().chain(self.release).all(|rate| rate > 0.0 && rate.is_finite())
+                && self.exponent > 0.0 && self.exponent.is_finite()
+                && (0.0..=1.0).contains(&self.expo)
+                && (0.0..1.0).contains(&self.deadzone)
+    }
+
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponseSettings {
+    pub thrust: AxisResponse,
+    pub strafe: AxisResponse,
+    pub ascend: AxisResponse,
+    pub yaw: AxisResponse,
+    pub pitch: AxisResponse,
+    pub roll: AxisResponse,
+}
+
+impl ResponseSettings {
+
+    /// Returns the name of the first axis having an invalid response.
+    pub(crate) fn invalid_axis(&self) -> Option<&'static str> {
+        let responses = [
+            ("thrust", &self.thrust),
+            ("strafe", &self.strafe),
+            ("ascend", &self.ascend),
+            ("yaw", &self.yaw),
+            ("pitch", &self.pitch),
+            ("roll", &self.roll),
+        ];
+        responses.iter()
+                .find(|(_, response)| !response.is_valid())
+                .map(|(name, _)| *name)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::GradientValue;
+
+    /// Advances `value` by `ticks` steps of 10 ms each.
+    fn run(value: &mut GradientValue, ticks: u32) {
+        for _ in 0..ticks {
+            value.update(0.01);
+        }
+    }
+
+    #[test]
+    fn separate_attack_and_release() {
+        let mut response = Response::linear(1.0);
+        response.attack = 2.0;
+        response.release = 4.0;
+        let mut value = GradientValue::with_response(response);
+
+        value.set(1.0);
+        run(&mut value, 25);
+        assert!((value.value - 0.5).abs() < 1e-3);
+
+        value.set(0.0);
+        run(&mut value, 10);
+        assert!((value.value - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn s_curve_takes_as_long_as_linear() {
+        let mut response = Response::linear(1.0);
+        response.easing = Easing::SCurve;
+        let mut value = GradientValue::with_response(response);
+
+        value.set(1.0);
+        run(&mut value, 25);
+        // slow start
+        assert!(value.value < 0.25);
+        run(&mut value, 25);
+        assert!((value.value - 0.5).abs() < 1e-3);
+        run(&mut value, 51);
+        assert!((value.value - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn s_curve_follows_changing_values() {
+        let mut response = Response::linear(1.0);
+        response.easing = Easing::SCurve;
+        let mut value = GradientValue::with_response(response.clone());
+
+        // an analog input changing a bit every tick, faster than the axis may follow
+        for tick in 1..=50_u8 {
+            value.set(f32::from(tick) * 0.02);
+            run(&mut value, 1);
+        }
+        assert!(value.value > 0.5);
+        run(&mut value, 50);
+        assert!((value.value - 1.0).abs() < f32::EPSILON);
+
+        // reversing in the middle of a ramp keeps moving on at first instead of starting over
+        let mut value = GradientValue::with_response(response);
+        value.set(1.0);
+        run(&mut value, 50);
+        value.set(-1.0);
+        run(&mut value, 1);
+        assert!(value.value > 0.51);
+        run(&mut value, 300);
+        assert!((value.value + 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn exponential_reaches_target() {
+        let mut response = Response::linear(5.0);
+        response.easing = Easing::Exponential;
+        let mut value = GradientValue::with_response(response);
+
+        value.set(-1.0);
+        run(&mut value, 10);
+        // fast start
+        assert!(value.value < -0.3);
+        run(&mut value, 500);
+        assert!((value.value + 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn curves_and_deadzone() {
+        let mut response = Response::linear(1.0);
+        response.curve = Curve::Power;
+        response.exponent = 2.0;
+        assert!((response.shape(-0.5) + 0.25).abs() < 1e-6);
+
+        response.curve = Curve::Expo;
+        response.expo = 1.0;
+        assert!((response.shape(0.5) - 0.125).abs() < 1e-6);
+        assert!((response.shape(1.0) - 1.0).abs() < 1e-6);
+
+        response.curve = Curve::Linear;
+        response.deadzone = 0.2;
+        assert!(response.shape(0.1).abs() < f32::EPSILON);
+        assert!((response.shape(0.6) - 0.5).abs() < 1e-6);
+    }
+}
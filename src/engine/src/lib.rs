@@ -1,9 +1,29 @@
 #![deny(clippy::pedantic)]
+#![allow(clippy::non_ascii_literal)]
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
-    }
-}
+//! The Reactor-Ls engine provides the window handling, the render loop, input processing and asset loading
+//! that games can be built upon.
+//!
+//! A game implements [`App`] and hands it over to [`Engine::run`].
+
+pub mod assets;
+pub mod camera;
+pub mod capture;
+pub mod display;
+pub mod input;
+pub mod logging;
+pub mod model;
+pub mod rotation;
+pub mod scene;
+pub mod settings;
+pub mod shader;
+pub mod texture;
+pub mod timing;
+pub mod viewport;
+
+mod app;
+
+pub use app::{App, Action, Engine, start_loop};
+
+/// A 4×4 matrix in the layout glium expects for uniforms.
+pub type GliumMatrix = [[f32; 4]; 4];
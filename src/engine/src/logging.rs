@@ -0,0 +1,367 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use directories::ProjectDirs;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde::{Deserialize, Serialize};
+
+/// Base name of the log files within the log directory
+pub const LOG_FILE_NAME: &str = "session";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSettings {
+    /// level for all modules without an explicit level
+    pub level: LevelFilter,
+    /// whether to write log records to the terminal (stderr)
+    pub terminal: bool,
+    /// whether to write log records to a file
+    pub file: bool,
+    /// size in bytes after which a new log file is started
+    pub max_file_size: u64,
+    /// number of log files to keep including the current one
+    pub max_files: u32,
+    /// levels for individual modules (and their submodules), e.g. `"reactor_ls_engine::input" = "trace"`
+    pub modules: BTreeMap<String, LevelFilter>,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            terminal: true,
+            file: true,
+            max_file_size: 10 * 1024 * 1024,
+            max_files: 5,
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+/// Decides which records to log based on their target module.
+struct Filter {
+    default: LevelFilter,
+    /// sorted by descending length so the most specific module matches first
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+
+    fn new(settings: &LogSettings) -> Self {
+        let mut modules: Vec<_> = settings.modules.iter()
+                .map(|(module, level)| (module.clone(), *level))
+                .collect();
+        modules.sort_by_key(|(module, _)| Reverse(module.len()));
+        Self { default: settings.level, modules }
+    }
+
+    fn level(&self, target: &str) -> LevelFilter {
+        self.modules.iter()
+                .find(|(module, _)| target == module || (target.starts_with(module.as_str()) && target[module.len()..].starts_with("::")))
+                .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules.iter().map(|(_, level)| *level).fold(self.default, std::cmp::max)
+    }
+
+}
+
+/// A log file that is rotated once it exceeds a given size.
+///
+/// The current file is `<name>.log`; older ones are renamed to `<name>.1.log`, `<name>.2.log` and so forth.
+struct LogFile {
+    dir: PathBuf,
+    name: String,
+    max_size: u64,
+    max_files: u32,
+    file: File,
+    size: u64,
+    /// written to the top of every new file
+    header: String,
+}
+
+impl LogFile {
+
+    /// Starts a new session by moving the previous log files out of the way.
+    fn create(dir: &Path, name: &str, max_size: u64, max_files: u32) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        rotate(dir, name, max_files)?;
+        let file = File::create(file_path(dir, name, 0))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            max_size,
+            max_files,
+            file,
+            size: 0,
+            header: String::new(),
+        })
+    }
+
+    fn path(&self) -> PathBuf {
+        file_path(&self.dir, &self.name, 0)
+    }
+
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        // a file containing nothing but the header won't get any emptier by rotating it
+        if self.size > self.header.len() as u64 && self.size + line.len() as u64 > self.max_size {
+            rotate(&self.dir, &self.name, self.max_files)?;
+            self.file = File::create(self.path())?;
+            self.size = 0;
+            self.file.write_all(self.header.as_bytes())?;
+            self.size += self.header.len() as u64;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Puts `header` in front of everything that has been logged into the current file so far.
+    fn set_header(&mut self, header: String) -> io::Result<()> {
+        let path = self.path();
+        let mut content = Vec::new();
+        File::open(&path)?.read_to_end(&mut content)?;
+
+        let mut file = OpenOptions::new().write(true).truncate(true).open(&path)?;
+        file.write_all(header.as_bytes())?;
+        file.write_all(&content)?;
+        file.seek(SeekFrom::End(0))?;
+
+        self.size = (header.len() + content.len()) as u64;
+        self.file = file;
+        self.header = header;
+        Ok(())
+    }
+
+}
+
+fn file_path(dir: &Path, name: &str, index: u32) -> PathBuf {
+    if index == 0 {
+        dir.join(format!("{name}.log"))
+    } else {
+        dir.join(format!("{name}.{index}.log"))
+    }
+}
+
+/// Shifts all existing log files by one index and removes the ones exceeding `max_files`.
+fn rotate(dir: &Path, name: &str, max_files: u32) -> io::Result<()> {
+    let oldest = file_path(dir, name, max_files.saturating_sub(1));
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+    for index in (0..max_files.saturating_sub(1)).rev() {
+        let path = file_path(dir, name, index);
+        if path.exists() {
+            fs::rename(path, file_path(dir, name, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+struct Logger {
+    filter: Filter,
+    terminal: bool,
+    file: Option<Mutex<LogFile>>,
+    start: Instant,
+}
+
+impl Logger {
+
+    fn format(&self, record: &Record<'_>) -> String {
+        let elapsed = self.start.elapsed();
+        format!("[{:>5}.{:03} {:<5} {}] {}\n",
+                elapsed.as_secs(), elapsed.subsec_millis(), record.level(), record.target(), record.args())
+    }
+
+}
+
+impl Log for Logger {
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.filter.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = self.format(record);
+        if self.terminal {
+            // there's no better place to report a failing terminal
+            let _ = io::stderr().write_all(line.as_bytes());
+        }
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                if let Err(err) = file.write(&line) {
+                    let _ = writeln!(io::stderr(), "could not write log file: {err}");
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.file.flush();
+            }
+        }
+        let _ = io::stderr().flush();
+    }
+
+}
+
+/// Forwards to the shared logger; required because the `log` crate takes ownership of the registered logger.
+struct SharedLogger(Arc<Logger>);
+
+impl Log for SharedLogger {
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        self.0.log(record);
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+
+}
+
+/// Everything that went wrong while setting up the logger.
+#[derive(Debug)]
+pub enum LogError {
+    /// The log file could not be created
+    Io(io::Error),
+    /// Another logger has been registered already
+    SetLogger(SetLoggerError),
+}
+
+impl std::fmt::Display for LogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::SetLogger(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for LogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::SetLogger(err) => Some(err),
+        }
+    }
+}
+
+/// Gives access to the installed logger after initialization.
+pub struct LogHandle {
+    logger: Arc<Logger>,
+}
+
+impl LogHandle {
+
+    /// Path of the current log file if logging into a file is enabled.
+    #[must_use]
+    pub fn file_path(&self) -> Option<PathBuf> {
+        self.logger.file.as_ref()
+                .and_then(|file| file.lock().ok())
+                .map(|file| file.path())
+    }
+
+    /// Puts `header` (e.g. the capabilities of the display) at the top of the current log file and of all files
+    /// started after rotation.
+    pub fn set_header(&self, header: &str) {
+        if let Some(file) = &self.logger.file {
+            if let Ok(mut file) = file.lock() {
+                let started = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+                let header = format!("session started at {} (seconds since epoch)\n{}\n", started, header.trim_end());
+                if let Err(err) = file.set_header(header) {
+                    let _ = writeln!(io::stderr(), "could not write log file header: {err}");
+                }
+            }
+        }
+    }
+
+}
+
+/// Installs the logger for the whole process; log files go into `dir` if given and enabled in `settings`.
+///
+/// # Errors
+///
+/// Fails if the log file cannot be created or another logger has been installed already.
+pub fn init(settings: &LogSettings, dir: Option<&Path>) -> Result<LogHandle, LogError> {
+    let file = match dir {
+        Some(dir) if settings.file => Some(Mutex::new(
+            LogFile::create(dir, LOG_FILE_NAME, settings.max_file_size, settings.max_files).map_err(LogError::Io)?
+        )),
+        _ => None,
+    };
+
+    let filter = Filter::new(settings);
+    let max_level = filter.max_level();
+    let logger = Arc::new(Logger {
+        filter,
+        terminal: settings.terminal,
+        file,
+        start: Instant::now(),
+    });
+
+    log::set_boxed_logger(Box::new(SharedLogger(Arc::clone(&logger)))).map_err(LogError::SetLogger)?;
+    log::set_max_level(max_level);
+
+    Ok(LogHandle { logger })
+}
+
+/// Returns the platform specific directory for the log files of `application` (e.g. `~/.local/share/<application>/logs`
+/// on Linux).
+#[must_use]
+pub fn default_dir(application: &str) -> Option<PathBuf> {
+    ProjectDirs::from("", "", application).map(|dirs| dirs.data_local_dir().join("logs"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_module_wins() {
+        let mut settings = LogSettings::default();
+        settings.modules.insert("engine".to_string(), LevelFilter::Debug);
+        settings.modules.insert("engine::input".to_string(), LevelFilter::Trace);
+        let filter = Filter::new(&settings);
+
+        assert_eq!(filter.level("engine"), LevelFilter::Debug);
+        assert_eq!(filter.level("engine::display"), LevelFilter::Debug);
+        assert_eq!(filter.level("engine::input::keyboard"), LevelFilter::Trace);
+        assert_eq!(filter.level("engineering"), LevelFilter::Info);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn rotates_by_size_and_count() {
+        let dir = std::env::temp_dir().join(format!("reactor-ls-log-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut file = LogFile::create(&dir, "test", 16, 3).unwrap();
+        file.set_header("header\n".to_string()).unwrap();
+        for _ in 0..5 {
+            file.write("0123456789\n").unwrap();
+        }
+
+        assert!(file_path(&dir, "test", 2).exists());
+        assert!(!file_path(&dir, "test", 3).exists());
+        assert_eq!(fs::read_to_string(file_path(&dir, "test", 0)).unwrap(), "header\n0123456789\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,372 @@
+use std::{error, fmt, io};
+
+use cgmath::{Deg, Matrix4};
+use gltf::buffer;
+use gltf::image::Source;
+use gltf::material::AlphaMode;
+use gltf::mesh::Mode;
+use gltf::texture::Info;
+use gltf::{Document, Gltf};
+use log::{debug, warn};
+
+use crate::model::{Mesh, Vertex, Weighting};
+
+/// Everything that went wrong while importing a glTF file.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file is not valid glTF
+    Gltf(gltf::Error),
+    /// An external buffer could not be read
+    Io(io::Error),
+    /// A buffer embedded as `data:` URI is not valid base64
+    Base64(base64::DecodeError),
+    /// A buffer refers to a URI that cannot be resolved
+    UnsupportedUri(String),
+    /// A buffer is shorter than declared or missing altogether
+    BufferTooShort { buffer: usize },
+    /// A primitive of the given mesh doesn't have any positions
+    MissingPositions { mesh: usize },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gltf(err) => write!(f, "{err}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Base64(err) => write!(f, "invalid embedded buffer: {err}"),
+            Self::UnsupportedUri(uri) => write!(f, "cannot resolve buffer URI `{uri}`"),
+            Self::BufferTooShort { buffer } => write!(f, "buffer {buffer} is shorter than declared"),
+            Self::MissingPositions { mesh } => write!(f, "a primitive of mesh {mesh} doesn't have any positions"),
+        }
+    }
+}
+
+impl error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Gltf(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Base64(err) => Some(err),
+            Self::UnsupportedUri(_) | Self::BufferTooShort { .. } | Self::MissingPositions { .. } => None,
+        }
+    }
+}
+
+impl From<gltf::Error> for ImportError {
+    fn from(err: gltf::Error) -> Self {
+        Self::Gltf(err)
+    }
+}
+
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<base64::DecodeError> for ImportError {
+    fn from(err: base64::DecodeError) -> Self {
+        Self::Base64(err)
+    }
+}
+
+/// Where the data of an image comes from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageSource {
+    /// an external file relative to the glTF file or a `data:` URI
+    Uri { uri: String, mime_type: Option<String> },
+    /// encoded image data stored within one of the buffers, as found in GLB files
+    Embedded { data: Vec<u8>, mime_type: String },
+}
+
+/// Refers to one of the images of a [`Model`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureRef {
+    /// index into [`Model::images`]
+    pub image: usize,
+    /// set of texture coordinates to use; only set 0 is being imported
+    pub tex_coord: u32,
+}
+
+/// How the alpha value of the base color is to be interpreted.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Alpha {
+    Opaque,
+    /// fully transparent below the cutoff, fully opaque otherwise
+    Mask { cutoff: f32 },
+    Blend,
+}
+
+/// Parameters of the metallic-roughness PBR model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: Option<String>,
+    /// linear RGBA
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// metalness in the blue and roughness in the green channel
+    pub metallic_roughness_texture: Option<TextureRef>,
+    pub normal_texture: Option<TextureRef>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<TextureRef>,
+    pub occlusion_strength: f32,
+    /// linear RGB
+    pub emissive: [f32; 3],
+    pub emissive_texture: Option<TextureRef>,
+    pub alpha: Alpha,
+    pub double_sided: bool,
+}
+
+/// A part of a mesh drawn with a single material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Primitive {
+    pub mesh: Mesh,
+    /// index into [`Model::materials`]; `None` selects the default material
+    pub material: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>,
+}
+
+/// An element of the transform tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub name: Option<String>,
+    /// transformation relative to the parent node
+    pub transform: Matrix4<f32>,
+    /// index into [`Model::meshes`]
+    pub mesh: Option<usize>,
+    /// indices into [`Model::nodes`]
+    pub children: Vec<usize>,
+}
+
+/// The content of a glTF file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Model {
+    pub meshes: Vec<ModelMesh>,
+    pub materials: Vec<Material>,
+    pub images: Vec<ImageSource>,
+    pub nodes: Vec<Node>,
+    /// the top level nodes of the default scene (or of the first scene if there's no default)
+    pub roots: Vec<usize>,
+}
+
+/// Imports a glTF or GLB file.
+///
+/// External buffers are read by `load`, which receives their URI, i.e. their path relative to the file. Texture
+/// coordinates are converted to the OpenGL convention with the origin at the bottom left. Primitives without normals
+/// get flat normals as demanded by the specification.
+///
+/// # Errors
+///
+/// Fails if `data` doesn't contain valid glTF, if a buffer cannot be read or a primitive has no positions.
+pub fn load_gltf<R, F>(data: &mut R, load: F) -> Result<Model, ImportError>
+where
+    R: io::Read + io::Seek,
+    F: FnMut(&str) -> io::Result<Vec<u8>>,
+{
+    let Gltf { document, blob } = Gltf::from_reader(data)?;
+    let buffers = load_buffers(&document, load, blob)?;
+
+    let meshes = document.meshes()
+            .map(|mesh| load_mesh(&mesh, &buffers))
+            .collect::<Result<Vec<_>, _>>()?;
+    let model = Model {
+        meshes,
+        materials: document.materials().map(|material| load_material(&material)).collect(),
+        images: load_images(&document, &buffers),
+        nodes: document.nodes().map(|node| load_node(&node)).collect(),
+        roots: document.default_scene().or_else(|| document.scenes().next())
+                .map_or_else(Vec::new, |scene| scene.nodes().map(|node| node.index()).collect()),
+    };
+    debug!("imported {} meshes, {} materials, {} images and {} nodes", model.meshes.len(), model.materials.len(), model.images.len(), model.nodes.len());
+    Ok(model)
+}
+
+/// Reads the content of all buffers, which may be stored in the GLB file itself, as `data:` URI or in external files.
+fn load_buffers<F>(document: &Document, mut load: F, mut blob: Option<Vec<u8>>) -> Result<Vec<Vec<u8>>, ImportError>
+where
+    F: FnMut(&str) -> io::Result<Vec<u8>>
+{
+    let mut buffers = Vec::new();
+    for buffer in document.buffers() {
+        let mut data = match buffer.source() {
+            buffer::Source::Bin => blob.take().ok_or(ImportError::BufferTooShort { buffer: buffer.index() })?,
+            buffer::Source::Uri(uri) => {
+                if let Some(data) = uri.strip_prefix("data:") {
+                    let (_, encoded) = data.split_once(";base64,").ok_or_else(|| ImportError::UnsupportedUri(uri.to_string()))?;
+                    base64::decode(encoded)?
+                } else if uri.contains("://") {
+                    return Err(ImportError::UnsupportedUri(uri.to_string()));
+                } else {
+                    load(uri)?
+                }
+            },
+        };
+        if data.len() < buffer.length() {
+            return Err(ImportError::BufferTooShort { buffer: buffer.index() });
+        }
+        // GLB chunks may be padded
+        data.truncate(buffer.length());
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+fn load_mesh(mesh: &gltf::Mesh<'_>, buffers: &[Vec<u8>]) -> Result<ModelMesh, ImportError> {
+    let mut primitives = Vec::new();
+    for primitive in mesh.primitives() {
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+
+        let positions = reader.read_positions().ok_or(ImportError::MissingPositions { mesh: mesh.index() })?;
+        let mut normals = reader.read_normals();
+        let has_normals = normals.is_some();
+        let mut tex_coords = reader.read_tex_coords(0).map(gltf::mesh::util::ReadTexCoords::into_f32);
+
+        let vertices: Vec<Vertex> = positions.map(|position| Vertex {
+            position,
+            normal: normals.as_mut().and_then(Iterator::next).unwrap_or([0.0, 0.0, 0.0]),
+            tex_coords: tex_coords.as_mut().and_then(Iterator::next).map_or([0.0, 0.0], |[u, v]| [u, 1.0 - v]),
+        }).collect();
+
+        #[allow(clippy::cast_possible_truncation)]
+        let indices: Vec<u32> = reader.read_indices()
+                .map_or_else(|| (0..vertices.len() as u32).collect(), |indices| indices.into_u32().collect());
+
+        let Some(indices) = triangulate(primitive.mode(), &indices) else {
+            warn!("skipping primitive {} of mesh {} with unsupported mode {:?}", primitive.index(), mesh.index(), primitive.mode());
+            continue;
+        };
+        if indices.iter().any(|&index| index as usize >= vertices.len()) {
+            warn!("skipping primitive {} of mesh {} with indices out of range", primitive.index(), mesh.index());
+            continue;
+        }
+
+        let mut mesh = Mesh::new(vertices, indices);
+        if !has_normals {
+            mesh.recompute_normals(Deg(0.0), Weighting::Angle);
+        }
+        primitives.push(Primitive { mesh, material: primitive.material().index() });
+    }
+
+    Ok(ModelMesh { name: mesh.name().map(String::from), primitives })
+}
+
+/// Converts the indices of any triangle based mode into a list of separate triangles.
+fn triangulate(mode: Mode, indices: &[u32]) -> Option<Vec<u32>> {
+    match mode {
+        Mode::Triangles => Some(indices[..indices.len() - indices.len() % 3].to_vec()),
+        Mode::TriangleStrip => Some(indices.windows(3).enumerate()
+                // every other triangle has to be flipped to keep the winding
+                .flat_map(|(n, corners)| if n % 2 == 0 { [corners[0], corners[1], corners[2]] } else { [corners[1], corners[0], corners[2]] })
+                .collect()),
+        Mode::TriangleFan => Some(indices.windows(2).skip(1)
+                .flat_map(|corners| [indices[0], corners[0], corners[1]])
+                .collect()),
+        Mode::Points | Mode::Lines | Mode::LineLoop | Mode::LineStrip => None,
+    }
+}
+
+fn load_material(material: &gltf::Material<'_>) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let texture_ref = |info: Info<'_>| TextureRef { image: info.texture().source().index(), tex_coord: info.tex_coord() };
+    Material {
+        name: material.name().map(String::from),
+        base_color: pbr.base_color_factor(),
+        base_color_texture: pbr.base_color_texture().map(texture_ref),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr.metallic_roughness_texture().map(texture_ref),
+        normal_texture: material.normal_texture().map(|normal| TextureRef { image: normal.texture().source().index(), tex_coord: normal.tex_coord() }),
+        normal_scale: material.normal_texture().map_or(1.0, |normal| normal.scale()),
+        occlusion_texture: material.occlusion_texture().map(|occlusion| TextureRef { image: occlusion.texture().source().index(), tex_coord: occlusion.tex_coord() }),
+        occlusion_strength: material.occlusion_texture().map_or(1.0, |occlusion| occlusion.strength()),
+        emissive: material.emissive_factor(),
+        emissive_texture: material.emissive_texture().map(texture_ref),
+        alpha: match material.alpha_mode() {
+            AlphaMode::Opaque => Alpha::Opaque,
+            AlphaMode::Mask => Alpha::Mask { cutoff: material.alpha_cutoff() },
+            AlphaMode::Blend => Alpha::Blend,
+        },
+        double_sided: material.double_sided(),
+    }
+}
+
+fn load_images(document: &Document, buffers: &[Vec<u8>]) -> Vec<ImageSource> {
+    document.images().map(|image| match image.source() {
+        Source::Uri { uri, mime_type } => ImageSource::Uri { uri: uri.to_string(), mime_type: mime_type.map(String::from) },
+        Source::View { view, mime_type } => {
+            let data = buffers[view.buffer().index()].get(view.offset()..view.offset() + view.length()).unwrap_or_default().to_vec();
+            ImageSource::Embedded { data, mime_type: mime_type.to_string() }
+        },
+    }).collect()
+}
+
+fn load_node(node: &gltf::Node<'_>) -> Node {
+    Node {
+        name: node.name().map(String::from),
+        transform: Matrix4::from(node.transform().matrix()),
+        mesh: node.mesh().map(|mesh| mesh.index()),
+        children: node.children().map(|child| child.index()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{InnerSpace, Vector3, Vector4};
+    use std::io::Cursor;
+
+    /// A single triangle in the xy plane without normals, translated by 2 along x, along with a red material.
+    ///
+    /// The buffer contains three positions followed by three u16 indices.
+    const TRIANGLE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [ { "nodes": [ 0 ] } ],
+        "nodes": [ { "name": "root", "translation": [ 2.0, 0.0, 0.0 ], "children": [ 1 ] }, { "mesh": 0 } ],
+        "meshes": [ { "name": "triangle", "primitives": [ { "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 } ] } ],
+        "materials": [ { "pbrMetallicRoughness": { "baseColorFactor": [ 1.0, 0.0, 0.0, 1.0 ], "metallicFactor": 0.5 } } ],
+        "buffers": [ { "byteLength": 42, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIA" } ],
+        "bufferViews": [ { "buffer": 0, "byteLength": 36 }, { "buffer": 0, "byteOffset": 36, "byteLength": 6 } ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [ 0.0, 0.0, 0.0 ], "max": [ 1.0, 1.0, 0.0 ] },
+            { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+        ]
+    }"#;
+
+    #[test]
+    fn imports_meshes_materials_and_nodes() {
+        let model = load_gltf(&mut Cursor::new(TRIANGLE), |_| Err(io::ErrorKind::NotFound.into())).unwrap();
+
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.name.as_deref(), Some("triangle"));
+        let primitive = &mesh.primitives[0];
+        assert_eq!(primitive.material, Some(0));
+        assert_eq!(primitive.mesh.triangle_count(), 1);
+        // flat normals have been generated
+        assert!(primitive.mesh.vertices().iter().all(|vertex| (Vector3::from(vertex.normal) - Vector3::unit_z()).magnitude() < 1e-6));
+
+        let material = &model.materials[0];
+        assert!((Vector4::from(material.base_color) - Vector4::new(1.0, 0.0, 0.0, 1.0)).magnitude() < 1e-6);
+        assert!((material.metallic - 0.5).abs() < f32::EPSILON);
+        assert_eq!(material.alpha, Alpha::Opaque);
+
+        assert_eq!(model.roots, vec![0]);
+        assert_eq!(model.nodes[0].children, vec![1]);
+        assert_eq!(model.nodes[1].mesh, Some(0));
+        assert_eq!(model.nodes[0].transform * Vector4::new(0.0, 0.0, 0.0, 1.0), Vector4::new(2.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn strips_and_fans_become_triangles() {
+        assert_eq!(triangulate(Mode::TriangleStrip, &[0, 1, 2, 3]), Some(vec![0, 1, 2, 2, 1, 3]));
+        assert_eq!(triangulate(Mode::TriangleFan, &[0, 1, 2, 3]), Some(vec![0, 1, 2, 0, 2, 3]));
+        assert_eq!(triangulate(Mode::Lines, &[0, 1]), None);
+    }
+}
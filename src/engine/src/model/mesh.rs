@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::{error, fmt};
+
+use cgmath::Rad;
+use glium::backend::Facade;
+use glium::index::{self, IndexBuffer, PrimitiveType};
+use glium::vertex::{self, VertexBuffer};
+
+use crate::model::{Vertex, Weighting, normals};
+
+/// Everything that went wrong while uploading a mesh to the GPU.
+#[derive(Debug)]
+pub enum UploadError {
+    /// The vertex buffer could not be created
+    Vertices(vertex::BufferCreationError),
+    /// The index buffer could not be created
+    Indices(index::BufferCreationError),
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Vertices(err) => write!(f, "could not create vertex buffer: {err}"),
+            Self::Indices(err) => write!(f, "could not create index buffer: {err}"),
+        }
+    }
+}
+
+impl error::Error for UploadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Vertices(err) => Some(err),
+            Self::Indices(err) => Some(err),
+        }
+    }
+}
+
+impl From<vertex::BufferCreationError> for UploadError {
+    fn from(err: vertex::BufferCreationError) -> Self {
+        Self::Vertices(err)
+    }
+}
+
+impl From<index::BufferCreationError> for UploadError {
+    fn from(err: index::BufferCreationError) -> Self {
+        Self::Indices(err)
+    }
+}
+
+/// A triangle mesh in main memory; every three indices form a triangle.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl Mesh {
+
+    /// Creates a mesh from shared vertices and the indices of its triangles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of indices is not a multiple of three or an index is out of range.
+    #[must_use]
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        assert!(indices.len().is_multiple_of(3), "number of indices must be a multiple of three");
+        assert!(indices.iter().all(|&index| (index as usize) < vertices.len()), "index out of range");
+        Self { vertices, indices }
+    }
+
+    /// Creates a mesh from a list of triangles with three vertices each, welding identical vertices.
+    #[must_use]
+    pub fn from_triangles<I>(vertices: I) -> Self
+    where
+        I: IntoIterator<Item = Vertex>
+    {
+        let mut welder = Welder::new(0.0);
+        for vertex in vertices {
+            welder.push(vertex);
+        }
+        welder.finish()
+    }
+
+    #[must_use]
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    #[must_use]
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    #[must_use]
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Merges all vertices whose attributes differ by at most `tolerance` and drops the triangles that collapse
+    /// thereby.
+    pub fn weld(&mut self, tolerance: f32) {
+        let mut welder = Welder::new(tolerance);
+        for &index in &self.indices {
+            welder.push(self.vertices[index as usize]);
+        }
+        *self = welder.finish();
+    }
+
+    /// Replaces all normals by normals derived from the triangles, regardless of what has been loaded.
+    ///
+    /// Each vertex gets the weighted average normal of the adjacent triangles, leaving out those whose orientation
+    /// differs from the vertex' own triangle by more than `crease_angle`. A crease angle of zero results in flat
+    /// shading, while 180° smoothes everything. Triangles are expected to be wound counter-clockwise when seen from
+    /// the front.
+    pub fn recompute_normals<A: Into<Rad<f32>>>(&mut self, crease_angle: A, weighting: Weighting) {
+        *self = normals::smooth(&self.vertices, &self.indices, crease_angle.into(), weighting);
+    }
+
+    /// Creates the vertex and index buffers to draw this mesh as `TrianglesList`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the buffers cannot be created.
+    pub fn upload<F>(&self, facade: &F) -> Result<MeshBuffers, UploadError>
+    where
+        F: Facade + ?Sized
+    {
+        Ok(MeshBuffers {
+            vertices: VertexBuffer::new(facade, &self.vertices)?,
+            indices: IndexBuffer::new(facade, PrimitiveType::TrianglesList, &self.indices)?,
+        })
+    }
+
+    /// Drops all vertices not being referenced by any triangle.
+    #[allow(clippy::cast_possible_truncation)]
+    fn remove_unused_vertices(&mut self) {
+        let mut remap = vec![u32::MAX; self.vertices.len()];
+        let mut vertices = Vec::new();
+        for index in &mut self.indices {
+            let target = &mut remap[*index as usize];
+            if *target == u32::MAX {
+                *target = vertices.len() as u32;
+                vertices.push(self.vertices[*index as usize]);
+            }
+            *index = *target;
+        }
+        self.vertices = vertices;
+    }
+
+}
+
+/// The GPU side of a [`Mesh`].
+pub struct MeshBuffers {
+    pub vertices: VertexBuffer<Vertex>,
+    pub indices: IndexBuffer<u32>,
+}
+
+/// Builds a [`Mesh`] triangle by triangle while merging vertices that are close to each other.
+///
+/// Duplicates are never stored, so even huge meshes with many repeated vertices only take up the space of their
+/// distinct vertices.
+pub struct Welder {
+    tolerance: f32,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    /// indices of the vertices within each cell of a grid of `tolerance` sized cubes
+    cells: HashMap<[i64; 3], Vec<u32>>,
+    /// the vertices of the current incomplete triangle
+    triangle: Vec<u32>,
+    /// whether a triangle has been dropped, possibly leaving unused vertices behind
+    dropped: bool,
+}
+
+impl Welder {
+
+    /// Creates an empty mesh; vertices will be merged if none of their attributes differ by more than `tolerance`.
+    #[must_use]
+    pub fn new(tolerance: f32) -> Self {
+        Self {
+            tolerance: tolerance.max(0.0),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            cells: HashMap::new(),
+            triangle: Vec::with_capacity(3),
+            dropped: false,
+        }
+    }
+
+    /// Adds the next vertex; every three vertices form a triangle.
+    pub fn push(&mut self, vertex: Vertex) {
+        let index = self.find_or_insert(vertex);
+        self.triangle.push(index);
+        if self.triangle.len() == 3 {
+            let (a, b, c) = (self.triangle[0], self.triangle[1], self.triangle[2]);
+            // triangles without an area can't be seen anyway
+            if a != b && b != c && c != a {
+                self.indices.extend_from_slice(&[a, b, c]);
+            } else {
+                self.dropped = true;
+            }
+            self.triangle.clear();
+        }
+    }
+
+    /// Returns the mesh built so far; an incomplete last triangle will be dropped.
+    #[must_use]
+    pub fn finish(self) -> Mesh {
+        let mut mesh = Mesh { vertices: self.vertices, indices: self.indices };
+        if self.dropped || !self.triangle.is_empty() {
+            mesh.remove_unused_vertices();
+        }
+        mesh
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn find_or_insert(&mut self, vertex: Vertex) -> u32 {
+        let cell = self.cell(vertex.position);
+        let tolerance = self.tolerance;
+        let found = self.neighbours(cell)
+                .filter_map(|neighbour| self.cells.get(&neighbour))
+                .flatten()
+                .copied()
+                .find(|&index| is_close(&self.vertices[index as usize], &vertex, tolerance));
+
+        found.unwrap_or_else(|| {
+            let index = self.vertices.len() as u32;
+            self.vertices.push(vertex);
+            self.cells.entry(cell).or_default().push(index);
+            index
+        })
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn cell(&self, position: [f32; 3]) -> [i64; 3] {
+        if self.tolerance > 0.0 {
+            position.map(|coordinate| (coordinate / self.tolerance).floor() as i64)
+        } else {
+            // only identical vertices will be merged; -0.0 and 0.0 are considered identical
+            position.map(|coordinate| i64::from((coordinate + 0.0).to_bits()))
+        }
+    }
+
+    /// Returns all cells that may contain vertices within the tolerance of the given cell.
+    fn neighbours(&self, cell: [i64; 3]) -> impl Iterator<Item = [i64; 3]> {
+        let range = if self.tolerance > 0.0 { -1..=1 } else { 0..=0 };
+        let [x, y, z] = cell;
+        range.clone().flat_map(move |dx| {
+            let range = range.clone();
+            range.clone().flat_map(move |dy| range.clone().map(move |dz| [x + dx, y + dy, z + dz]))
+        })
+    }
+
+}
+
+fn is_close(a: &Vertex, b: &Vertex, tolerance: f32) -> bool {
+    let close = |a: &[f32], b: &[f32]| a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() <= tolerance);
+    close(&a.position, &b.position) && close(&a.normal, &b.normal) && close(&a.tex_coords, &b.tex_coords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32, z: f32) -> Vertex {
+        Vertex { position: [x, y, z], normal: [0.0, 0.0, 1.0], tex_coords: [0.0, 0.0] }
+    }
+
+    /// Two triangles forming a square, as an STL file would contain them.
+    fn square(offset: f32) -> Vec<Vertex> {
+        vec![
+            vertex(0.0, 0.0, 0.0), vertex(1.0, 0.0, 0.0), vertex(1.0, 1.0, 0.0),
+            vertex(offset, 0.0, 0.0), vertex(1.0, 1.0, offset), vertex(0.0, 1.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn identical_vertices_are_shared() {
+        let mesh = Mesh::from_triangles(square(0.0));
+        assert_eq!(mesh.vertices().len(), 4);
+        assert_eq!(mesh.indices(), &[0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn welds_within_tolerance() {
+        let mut mesh = Mesh::from_triangles(square(1e-4));
+        assert_eq!(mesh.vertices().len(), 6);
+
+        mesh.weld(1e-3);
+        assert_eq!(mesh.vertices().len(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+    }
+
+    #[test]
+    fn different_normals_are_kept_apart() {
+        let mut vertices = square(0.0);
+        vertices[3].normal = [0.0, 1.0, 0.0];
+        assert_eq!(Mesh::from_triangles(vertices).vertices().len(), 5);
+    }
+
+    #[test]
+    fn collapsed_triangles_are_dropped() {
+        let mut mesh = Mesh::from_triangles(vec![
+            vertex(0.0, 0.0, 0.0), vertex(1.0, 0.0, 0.0), vertex(1.0, 1.0, 0.0),
+            vertex(5.0, 5.0, 5.0), vertex(5.0, 5.0, 5.01), vertex(6.0, 5.0, 5.0),
+        ]);
+        mesh.weld(0.1);
+        assert_eq!(mesh.triangle_count(), 1);
+        assert_eq!(mesh.vertices().len(), 3);
+    }
+}
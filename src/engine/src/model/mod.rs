@@ -0,0 +1,50 @@
+use std::io;
+
+use glium::implement_vertex;
+
+pub use self::gltf::{Alpha, ImageSource, ImportError, Material, Model, ModelMesh, Node, Primitive, TextureRef, load_gltf};
+pub use mesh::{Mesh, MeshBuffers, UploadError, Welder};
+pub use normals::Weighting;
+pub use wavefront::{PhongMaterial, WavefrontError, WavefrontModel, WavefrontPart, load_wavefront};
+
+mod gltf;
+mod mesh;
+mod normals;
+mod wavefront;
+
+/// A single vertex as it is being uploaded to the GPU.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    /// OpenGL convention with the origin at the bottom left
+    tex_coords: [f32; 2],
+}
+
+implement_vertex!(Vertex, position, normal, tex_coords);
+
+/// Reads an STL file into a mesh; vertices are shared among the triangles of the same facet normal.
+///
+/// # Errors
+///
+/// Fails if `data` cannot be read or does not contain a valid STL file.
+pub fn load_stl<R>(data: &mut R) -> io::Result<Mesh>
+where
+    R: io::Read + io::Seek
+{
+    let mesh = stl_io::read_stl(data)?;
+
+    let mut welder = Welder::new(0.0);
+    for triangle in &mesh.faces {
+        for &vertex_index in &triangle.vertices {
+            let position = mesh.vertices[vertex_index];
+            welder.push(Vertex {
+                position: [position[0], position[1], position[2]], // TODO https://github.com/hmeyer/stl_io/pull/9
+                normal: [triangle.normal[0], triangle.normal[1], triangle.normal[2]],
+                tex_coords: [0.0, 0.0],
+            });
+        }
+    }
+    Ok(welder.finish())
+}
+
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use cgmath::{InnerSpace, Rad, Vector3, Zero};
+
+use crate::model::{Mesh, Vertex, Welder};
+
+/// How much each adjacent triangle contributes to a smooth vertex normal.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Weighting {
+    /// larger triangles contribute more
+    Area,
+    /// triangles contribute according to their angle at the vertex, which doesn't depend on how a surface has been
+    /// split into triangles
+    Angle,
+}
+
+/// Orientation and size of a single triangle.
+struct Face {
+    /// unit normal following the counter-clockwise winding; zero for degenerate triangles
+    normal: Vector3<f32>,
+    area: f32,
+    /// interior angle at each corner in radians
+    angles: [f32; 3],
+}
+
+impl Face {
+
+    fn new(corners: [Vector3<f32>; 3]) -> Self {
+        let [a, b, c] = corners;
+        let cross = (b - a).cross(c - a);
+        let length = cross.magnitude();
+        if length <= 0.0 || !length.is_finite() {
+            return Self { normal: Vector3::zero(), area: 0.0, angles: [0.0; 3] };
+        }
+
+        let angle = |corner: Vector3<f32>, previous: Vector3<f32>, next: Vector3<f32>| (previous - corner).angle(next - corner).0;
+        Self {
+            normal: cross / length,
+            area: length / 2.0,
+            angles: [angle(a, c, b), angle(b, a, c), angle(c, b, a)],
+        }
+    }
+
+    fn is_degenerate(&self) -> bool {
+        self.area <= 0.0
+    }
+
+}
+
+/// Replaces the normals of all vertices by the weighted average of the adjacent triangles' normals.
+///
+/// Triangles whose normals differ from the one of the vertex' own triangle by more than `crease_angle` are left
+/// out, so edges sharper than that stay hard. Vertices sharing a position and the resulting normal are welded.
+pub(crate) fn smooth(vertices: &[Vertex], indices: &[u32], crease_angle: Rad<f32>, weighting: Weighting) -> Mesh {
+    let triangles: Vec<[u32; 3]> = indices.chunks_exact(3).map(|corners| [corners[0], corners[1], corners[2]]).collect();
+    let position = |index: u32| Vector3::from(vertices[index as usize].position);
+    let faces: Vec<Face> = triangles.iter()
+            .map(|corners| Face::new(corners.map(position)))
+            .collect();
+
+    // all triangles touching a position along with the corner that touches it
+    let mut adjacent: HashMap<[u32; 3], Vec<(usize, usize)>> = HashMap::new();
+    for (triangle, corners) in triangles.iter().enumerate() {
+        for (corner, &index) in corners.iter().enumerate() {
+            adjacent.entry(position_key(vertices[index as usize].position)).or_default().push((triangle, corner));
+        }
+    }
+
+    let min_cos = crease_angle.0.cos();
+    let mut welder = Welder::new(0.0);
+    for (triangle, corners) in triangles.iter().enumerate() {
+        let face = &faces[triangle];
+        for &index in corners {
+            let vertex = vertices[index as usize];
+            let normal = adjacent[&position_key(vertex.position)].iter()
+                    .map(|&(other, corner)| (&faces[other], corner))
+                    .filter(|(other, _)| !other.is_degenerate() && other.normal.dot(face.normal) >= min_cos)
+                    .map(|(other, corner)| other.normal * match weighting {
+                        Weighting::Area => other.area,
+                        Weighting::Angle => other.angles[corner],
+                    })
+                    .fold(Vector3::zero(), |sum, normal| sum + normal);
+
+            let normal = if normal.magnitude2() > 0.0 {
+                normal.normalize()
+            } else if face.is_degenerate() {
+                // nothing to derive a normal from
+                Vector3::from(vertex.normal)
+            } else {
+                face.normal
+            };
+            welder.push(Vertex { normal: normal.into(), ..vertex });
+        }
+    }
+    welder.finish()
+}
+
+/// Identifies a position exactly; -0.0 and 0.0 are considered identical.
+fn position_key(position: [f32; 3]) -> [u32; 3] {
+    position.map(|coordinate| (coordinate + 0.0).to_bits())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Deg;
+
+    fn vertex(x: f32, y: f32, z: f32) -> Vertex {
+        // deliberately broken normal as found in some STL files
+        Vertex { position: [x, y, z], normal: [0.0, 0.0, 0.0], tex_coords: [0.0, 0.0] }
+    }
+
+    /// Two triangles sharing the edge along the y axis; the second one is tilted by `angle` degrees.
+    fn fold(angle: f32) -> Mesh {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Mesh::from_triangles(vec![
+            vertex(0.0, 0.0, 0.0), vertex(0.0, 1.0, 0.0), vertex(-1.0, 0.0, 0.0),
+            vertex(0.0, 0.0, 0.0), vertex(cos, 0.0, sin), vertex(0.0, 1.0, 0.0),
+        ])
+    }
+
+    #[test]
+    fn face_normals_follow_winding() {
+        let mut mesh = fold(0.0);
+        mesh.recompute_normals(Deg(0.0), Weighting::Angle);
+        for vertex in mesh.vertices() {
+            assert!((Vector3::from(vertex.normal) - Vector3::new(0.0, 0.0, 1.0)).magnitude() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn crease_angle_keeps_hard_edges() {
+        let mut mesh = fold(20.0);
+        mesh.recompute_normals(Deg(10.0), Weighting::Angle);
+        assert_eq!(mesh.vertices().len(), 6);
+
+        let mut mesh = fold(20.0);
+        mesh.recompute_normals(Deg(30.0), Weighting::Angle);
+        assert_eq!(mesh.vertices().len(), 4);
+
+        // the shared edge gets the normal halfway in between
+        let shared = mesh.vertices().iter().find(|vertex| vertex.position == [0.0, 0.0, 0.0]).unwrap();
+        let expected = Vector3::new(-(10.0_f32).to_radians().sin(), 0.0, (10.0_f32).to_radians().cos());
+        assert!((Vector3::from(shared.normal) - expected).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn area_weighting_prefers_larger_triangles() {
+        let mut mesh = Mesh::from_triangles(vec![
+            vertex(0.0, 0.0, 0.0), vertex(0.0, 1.0, 0.0), vertex(-1.0, 0.0, 0.0),
+            vertex(0.0, 0.0, 0.0), vertex(4.0, 0.0, 1.0), vertex(0.0, 1.0, 0.0),
+        ]);
+        mesh.recompute_normals(Deg(90.0), Weighting::Area);
+        let shared = mesh.vertices().iter().find(|vertex| vertex.position == [0.0, 0.0, 0.0]).unwrap();
+        // closer to the normal of the larger, tilted triangle than to the z axis
+        assert!(shared.normal[0] < -0.1);
+    }
+}
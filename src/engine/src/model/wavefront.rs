@@ -0,0 +1,352 @@
+use std::io;
+use std::{error, fmt};
+
+use cgmath::{InnerSpace, Vector2, Vector3, Zero};
+use log::{debug, warn};
+use obj::{IndexTuple, LoadConfig, MtlError, ObjData, ObjError, ObjMaterial, SimplePolygon};
+
+use crate::model::{Mesh, Vertex, Welder};
+
+/// Everything that went wrong while importing a Wavefront file.
+#[derive(Debug)]
+pub enum WavefrontError {
+    /// The file cannot be read or is not a valid Wavefront file
+    Obj(ObjError),
+    /// A material library referenced by `mtllib` cannot be read or is invalid
+    Mtl { library: String, error: MtlError },
+    /// A face refers to a vertex attribute that doesn't exist; `index` is zero-based
+    IndexOutOfRange { group: String, attribute: &'static str, index: usize },
+}
+
+impl fmt::Display for WavefrontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Obj(err) => write!(f, "{err}"),
+            Self::Mtl { library, error } => write!(f, "could not load material library `{library}`: {error}"),
+            Self::IndexOutOfRange { group, attribute, index } => {
+                write!(f, "a face of group `{group}` refers to {attribute} {} which doesn't exist", index + 1)
+            },
+        }
+    }
+}
+
+impl error::Error for WavefrontError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Obj(err) => Some(err),
+            Self::Mtl { error, .. } => Some(error),
+            Self::IndexOutOfRange { .. } => None,
+        }
+    }
+}
+
+impl From<ObjError> for WavefrontError {
+    fn from(err: ObjError) -> Self {
+        Self::Obj(err)
+    }
+}
+
+/// Parameters of the Phong model as found in MTL files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhongMaterial {
+    pub name: String,
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub emissive: [f32; 3],
+    /// specular exponent
+    pub shininess: f32,
+    /// 1 is fully opaque
+    pub opacity: f32,
+    /// texture paths relative to the material library
+    pub diffuse_map: Option<String>,
+    pub specular_map: Option<String>,
+    pub normal_map: Option<String>,
+    pub opacity_map: Option<String>,
+}
+
+impl From<&obj::Material> for PhongMaterial {
+    fn from(material: &obj::Material) -> Self {
+        Self {
+            name: material.name.clone(),
+            ambient: material.ka.unwrap_or([0.0; 3]),
+            diffuse: material.kd.unwrap_or([0.8; 3]),
+            specular: material.ks.unwrap_or([0.0; 3]),
+            emissive: material.ke.unwrap_or([0.0; 3]),
+            shininess: material.ns.unwrap_or(1.0),
+            // `Tr` is the inverse of `d` used by some exporters
+            opacity: material.d.or_else(|| material.tr.map(|transparency| 1.0 - transparency)).unwrap_or(1.0),
+            diffuse_map: material.map_kd.clone(),
+            specular_map: material.map_ks.clone(),
+            normal_map: material.map_bump.clone(),
+            opacity_map: material.map_d.clone(),
+        }
+    }
+}
+
+/// The faces of a single group drawn with a single material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WavefrontPart {
+    /// name given by `o`
+    pub object: String,
+    /// name given by `g`
+    pub group: String,
+    pub mesh: Mesh,
+    /// index into [`WavefrontModel::materials`]; `None` if no or an unknown material has been assigned
+    pub material: Option<usize>,
+}
+
+/// The content of a Wavefront file along with its material libraries.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WavefrontModel {
+    pub parts: Vec<WavefrontPart>,
+    pub materials: Vec<PhongMaterial>,
+}
+
+impl WavefrontModel {
+
+    /// Combines the meshes of all parts into one, dropping the material assignments.
+    #[must_use]
+    pub fn merged_mesh(&self) -> Mesh {
+        Mesh::from_triangles(self.parts.iter()
+                .flat_map(|part| part.mesh.indices().iter().map(move |&index| part.mesh.vertices()[index as usize])))
+    }
+
+}
+
+/// Imports a Wavefront OBJ file.
+///
+/// Material libraries are read by `load`, which receives their path relative to the file. Polygons are split into
+/// triangles and corners without a normal get the normal of their polygon, which can be smoothed with
+/// [`Mesh::recompute_normals`]. Statements not supported by the parser, such as free-form geometry, are ignored.
+///
+/// # Errors
+///
+/// Fails if `data` doesn't contain a valid Wavefront file, if a material library cannot be loaded or if a face
+/// refers to a vertex attribute that doesn't exist.
+pub fn load_wavefront<R, F>(data: &mut R, load: F) -> Result<WavefrontModel, WavefrontError>
+where
+    R: io::Read,
+    F: FnMut(&str) -> io::Result<Vec<u8>>,
+{
+    let data = ObjData::load_buf_with_config(data, LoadConfig { strict: false })?;
+    let materials = load_materials(&data, load)?;
+
+    let mut parts = Vec::new();
+    for object in &data.objects {
+        for group in object.groups.iter().filter(|group| !group.polys.is_empty()) {
+            let material = group.material.as_ref().and_then(|material| {
+                let name = match material {
+                    ObjMaterial::Ref(name) => name.as_str(),
+                    ObjMaterial::Mtl(material) => material.name.as_str(),
+                };
+                let index = materials.iter().position(|material| material.name == name);
+                if index.is_none() {
+                    warn!("group `{}` uses unknown material `{name}`", group.name);
+                }
+                index
+            });
+            let mesh = load_group(&data, &group.polys)
+                    .map_err(|(attribute, index)| WavefrontError::IndexOutOfRange { group: group.name.clone(), attribute, index })?;
+            parts.push(WavefrontPart { object: object.name.clone(), group: group.name.clone(), mesh, material });
+        }
+    }
+
+    debug!("imported {} parts and {} materials", parts.len(), materials.len());
+    Ok(WavefrontModel { parts, materials })
+}
+
+fn load_materials<F>(data: &ObjData, mut load: F) -> Result<Vec<PhongMaterial>, WavefrontError>
+where
+    F: FnMut(&str) -> io::Result<Vec<u8>>
+{
+    let mut materials = Vec::new();
+    for library in &data.material_libs {
+        let error = |error| WavefrontError::Mtl { library: library.filename.clone(), error };
+        let content = load(&library.filename).map_err(|err| error(MtlError::Io(err)))?;
+        let mut library = library.clone();
+        library.reload(content.as_slice()).map_err(error)?;
+        materials.extend(library.materials.iter().map(|material| PhongMaterial::from(&**material)));
+    }
+    Ok(materials)
+}
+
+/// Triangulates the polygons of a group; fails with the attribute and index of a dangling reference.
+fn load_group(data: &ObjData, polygons: &[SimplePolygon]) -> Result<Mesh, (&'static str, usize)> {
+    fn get<T: Copy>(values: &[T], index: usize, attribute: &'static str) -> Result<T, (&'static str, usize)> {
+        values.get(index).copied().ok_or((attribute, index))
+    }
+
+    let mut welder = Welder::new(0.0);
+    let mut vertices = Vec::new();
+    let mut missing_normals = Vec::new();
+    for polygon in polygons {
+        vertices.clear();
+        missing_normals.clear();
+        for &IndexTuple(position, texture, normal) in &polygon.0 {
+            missing_normals.push(normal.is_none());
+            vertices.push(Vertex {
+                position: get(&data.position, position, "position")?,
+                normal: normal.map(|index| get(&data.normal, index, "normal")).transpose()?.unwrap_or([0.0; 3]),
+                // same convention as OpenGL, no need to flip
+                tex_coords: texture.map(|index| get(&data.texture, index, "texture coordinate")).transpose()?.unwrap_or([0.0; 2]),
+            });
+        }
+
+        let positions: Vec<Vector3<f32>> = vertices.iter().map(|vertex| Vector3::from(vertex.position)).collect();
+        // corners without a normal of their own get the flat normal of the polygon
+        if missing_normals.contains(&true) {
+            let normal = polygon_normal(&positions);
+            let normal = if normal.is_zero() { normal } else { normal.normalize() };
+            for (vertex, _) in vertices.iter_mut().zip(&missing_normals).filter(|(_, &missing)| missing) {
+                vertex.normal = normal.into();
+            }
+        }
+
+        for triangle in triangulate(&positions) {
+            for corner in triangle {
+                welder.push(vertices[corner]);
+            }
+        }
+    }
+    Ok(welder.finish())
+}
+
+/// Returns the normal of a polygon scaled by twice its area, using Newell's method which works for concave and
+/// slightly non-planar polygons as well.
+fn polygon_normal(positions: &[Vector3<f32>]) -> Vector3<f32> {
+    positions.iter()
+            .zip(positions.iter().cycle().skip(1))
+            .fold(Vector3::zero(), |normal, (a, b)| normal + Vector3::new(
+                (a.y - b.y) * (a.z + b.z),
+                (a.z - b.z) * (a.x + b.x),
+                (a.x - b.x) * (a.y + b.y),
+            ))
+}
+
+/// Splits a polygon into triangles by ear clipping, keeping its winding; returns indices into `positions`.
+///
+/// Concave polygons are handled as long as they are roughly planar. Self-intersecting polygons result in overlapping
+/// triangles but never in missing ones.
+fn triangulate(positions: &[Vector3<f32>]) -> Vec<[usize; 3]> {
+    match positions.len() {
+        0..=2 => return Vec::new(),
+        3 => return vec![[0, 1, 2]],
+        _ => (),
+    }
+
+    let normal = polygon_normal(positions);
+    // drop the dominant axis of the normal, swapping the others if necessary to keep the polygon counter-clockwise
+    let axis = if normal.x.abs() >= normal.y.abs() && normal.x.abs() >= normal.z.abs() {
+        0
+    } else if normal.y.abs() >= normal.z.abs() {
+        1
+    } else {
+        2
+    };
+    let flip = normal[axis] < 0.0;
+    let points: Vec<Vector2<f32>> = positions.iter().map(|position| {
+        let (u, v) = match axis {
+            0 => (position.y, position.z),
+            1 => (position.z, position.x),
+            _ => (position.x, position.y),
+        };
+        if flip { Vector2::new(v, u) } else { Vector2::new(u, v) }
+    }).collect();
+
+    let cross = |a: usize, b: usize, c: usize| (points[b] - points[a]).perp_dot(points[c] - points[a]);
+    let mut remaining: Vec<usize> = (0..positions.len()).collect();
+    let mut triangles = Vec::with_capacity(positions.len() - 2);
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corners = |i: usize| (remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]);
+        let ear = (0..count).find(|&i| {
+            let (a, b, c) = corners(i);
+            cross(a, b, c) > 0.0 && !remaining.iter()
+                    .filter(|&&other| other != a && other != b && other != c)
+                    .any(|&other| cross(a, b, other) >= 0.0 && cross(b, c, other) >= 0.0 && cross(c, a, other) >= 0.0)
+        });
+        // degenerate polygons don't have proper ears, so just cut off any corner
+        let ear = ear.unwrap_or(0);
+        let (a, b, c) = corners(ear);
+        triangles.push([a, b, c]);
+        remaining.remove(ear);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn no_files(_: &str) -> io::Result<Vec<u8>> {
+        Err(io::ErrorKind::NotFound.into())
+    }
+
+    #[test]
+    fn concave_polygons_are_triangulated() {
+        // an arrow pointing to the left, wound clockwise and concave at index 4
+        let arrow: Vec<Vector3<f32>> = [[0.0, 0.0], [0.0, 2.0], [1.0, 1.0], [3.0, 1.0], [1.0, 0.5], [3.0, 0.0]].iter()
+                .map(|&[x, y]| Vector3::new(x, y, 0.0))
+                .collect();
+        let triangles = triangulate(&arrow);
+        assert_eq!(triangles.len(), 4);
+
+        let area = |[a, b, c]: [usize; 3]| (arrow[b] - arrow[a]).cross(arrow[c] - arrow[a]).z / 2.0;
+        // all triangles keep the clockwise winding and together cover the polygon
+        assert!(triangles.iter().all(|&triangle| area(triangle) < 0.0));
+        assert!((triangles.iter().map(|&triangle| area(triangle)).sum::<f32>() + 2.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn imports_parts_with_materials() {
+        let obj = "mtllib test.mtl\no quad\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 1\n\
+                   usemtl red\nf 1/1 2/1 3/2 4/2\ng other\nusemtl blue\nf -4 -3 -2\n";
+        let mut libraries = Vec::new();
+        let model = load_wavefront(&mut Cursor::new(obj), |name| {
+            libraries.push(name.to_string());
+            Ok(b"newmtl red\nKd 1 0 0\nd 0.5\nmap_Kd red.png\n".to_vec())
+        }).unwrap();
+        assert_eq!(libraries, ["test.mtl"]);
+
+        assert_eq!(model.materials.len(), 1);
+        assert_eq!(model.materials[0].diffuse_map.as_deref(), Some("red.png"));
+        assert!((model.materials[0].opacity - 0.5).abs() < f32::EPSILON);
+
+        assert_eq!(model.parts.len(), 2);
+        let quad = &model.parts[0];
+        assert_eq!((quad.object.as_str(), quad.material), ("quad", Some(0)));
+        assert_eq!(quad.mesh.triangle_count(), 2);
+        assert!(quad.mesh.vertices().iter().all(|vertex| (vertex.normal[2] - 1.0).abs() < 1e-6));
+        // unknown materials are tolerated
+        assert_eq!(model.parts[1].material, None);
+        assert_eq!(model.merged_mesh().triangle_count(), 3);
+    }
+
+    #[test]
+    fn only_missing_normals_are_filled_in() {
+        // a quad in the xy plane whose first corner has a normal of its own, followed by a triangle without normals
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 1 0\nf 1//1 2 3 4\nf 1 3 4\n";
+        let model = load_wavefront(&mut Cursor::new(obj), no_files).unwrap();
+        let mesh = &model.parts[0].mesh;
+
+        let close = |a: [f32; 3], b: [f32; 3]| (Vector3::from(a) - Vector3::from(b)).magnitude() < 1e-6;
+        let normals_at = |position: [f32; 3]| mesh.vertices().iter()
+                .filter(move |vertex| close(vertex.position, position))
+                .map(|vertex| vertex.normal);
+        assert!(normals_at([0.0, 0.0, 0.0]).any(|normal| close(normal, [0.0, 1.0, 0.0])));
+        assert!(normals_at([0.0, 0.0, 0.0]).any(|normal| close(normal, [0.0, 0.0, 1.0])));
+        assert!(normals_at([1.0, 0.0, 0.0]).all(|normal| close(normal, [0.0, 0.0, 1.0])));
+    }
+
+    #[test]
+    fn dangling_references_are_errors() {
+        let result = load_wavefront(&mut Cursor::new("v 0 0 0\nf 1 2 3\n"), no_files);
+        assert!(matches!(result, Err(WavefrontError::IndexOutOfRange { attribute: "position", index: 1, .. })));
+
+        let result = load_wavefront(&mut Cursor::new("v 0 0 0\nf 1 x 3\n"), no_files);
+        assert!(matches!(result, Err(WavefrontError::Obj(_))));
+    }
+}
@@ -0,0 +1,133 @@
+//! Orientations of cameras and rigid bodies as unit quaternions.
+//!
+//! Unlike rotation matrices, quaternions only need to be rescaled to stay valid after many small rotations have been
+//! accumulated, and they can be interpolated along the shortest arc between two orientations. Orientations follow the
+//! camera convention: x points to the right, y upwards and the object looks along its negative z axis.
+
+use cgmath::{InnerSpace, Matrix3, Quaternion, Rad, Rotation3, Vector3};
+
+/// Turns `rotation` by `angular_velocity` (radians per second around the object's own x, y and z axes) for `time`
+/// seconds.
+///
+/// The result is exact for an angular velocity that is constant during `time` and is normalized to prevent drift.
+#[must_use]
+pub fn integrate(rotation: Quaternion<f32>, angular_velocity: Vector3<f32>, time: f32) -> Quaternion<f32> {
+    let speed = angular_velocity.magnitude();
+    if speed * time.abs() < 1e-9 {
+        return rotation;
+    }
+    let delta = Quaternion::from_axis_angle(angular_velocity / speed, Rad(speed * time));
+    (rotation * delta).normalize()
+}
+
+/// Returns the constant angular velocity (around the object's own axes) turning `from` into `to` within `time`
+/// seconds along the shortest arc; the inverse of [`integrate`].
+#[must_use]
+pub fn angular_velocity(from: Quaternion<f32>, to: Quaternion<f32>, time: f32) -> Vector3<f32> {
+    let delta = from.conjugate() * to;
+    // q and -q are the same orientation; the one with a positive scalar part is the shorter way
+    let delta = if delta.s < 0.0 { -delta } else { delta };
+    let sine = delta.v.magnitude();
+    if sine < 1e-9 || time <= 0.0 {
+        return Vector3::new(0.0, 0.0, 0.0);
+    }
+    let angle = 2.0 * sine.atan2(delta.s);
+    delta.v / sine * (angle / time)
+}
+
+/// Interpolates between `from` (`alpha` = 0.0) and `to` (`alpha` = 1.0) at constant angular speed along the shortest
+/// arc.
+#[must_use]
+pub fn slerp(from: Quaternion<f32>, to: Quaternion<f32>, alpha: f32) -> Quaternion<f32> {
+    let to = if from.dot(to) < 0.0 { -to } else { to };
+    from.slerp(to, alpha).normalize()
+}
+
+/// Returns the orientation looking along `direction` with its y axis pointing towards `up` as far as possible, or
+/// `None` if `direction` is zero or parallel to `up`.
+#[must_use]
+pub fn look_rotation(direction: Vector3<f32>, up: Vector3<f32>) -> Option<Quaternion<f32>> {
+    if direction.magnitude2() < 1e-12 {
+        return None;
+    }
+    let z = -direction.normalize();
+    let x = up.cross(z);
+    if x.magnitude2() < 1e-12 {
+        return None;
+    }
+    let x = x.normalize();
+    Some(from_axes(Matrix3::from_cols(x, z.cross(x), z)))
+}
+
+/// Converts a rotation matrix whose columns are the axes of the object into a quaternion.
+///
+/// The axes are made orthonormal first, keeping the direction of the z axis, so matrices that have drifted slightly
+/// are accepted as well.
+#[must_use]
+pub fn from_axes(axes: Matrix3<f32>) -> Quaternion<f32> {
+    let z = axes.z.normalize();
+    let x = axes.y.cross(z).normalize();
+    let y = z.cross(x);
+    Quaternion::from(Matrix3::from_cols(x, y, z)).normalize()
+}
+
+/// Converts a quaternion into a rotation matrix whose columns are the axes of the object.
+#[must_use]
+pub fn axes(rotation: Quaternion<f32>) -> Matrix3<f32> {
+    Matrix3::from(rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Rotation};
+
+    fn angle_between(a: Quaternion<f32>, b: Quaternion<f32>) -> f32 {
+        angular_velocity(a, b, 1.0).magnitude()
+    }
+
+    #[test]
+    fn integration_does_not_drift() {
+        let angular_velocity = Vector3::new(0.3, -1.1, 2.0);
+        let mut rotation = Quaternion::from_angle_x(Deg(30.0));
+        for _ in 0..100_000 {
+            rotation = integrate(rotation, angular_velocity, 1.0 / 60.0);
+        }
+        assert!((rotation.magnitude() - 1.0).abs() < 1e-5);
+
+        // the same turn in a single step
+        let expected = integrate(Quaternion::from_angle_x(Deg(30.0)), angular_velocity, 100_000.0 / 60.0);
+        assert!(angle_between(rotation, expected) < 1e-2);
+    }
+
+    #[test]
+    fn angular_velocity_reverses_integration() {
+        let from = Quaternion::from_angle_y(Deg(40.0));
+        let to = integrate(from, Vector3::new(0.0, 0.0, 1.5), 0.5);
+        assert!((angular_velocity(from, to, 0.5) - Vector3::new(0.0, 0.0, 1.5)).magnitude() < 1e-4);
+        assert!((angular_velocity(from, -to, 0.5) - Vector3::new(0.0, 0.0, 1.5)).magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn slerp_takes_the_shortest_arc() {
+        let from = Quaternion::from_angle_y(Deg(10.0));
+        // the same orientation as 50° with the opposite sign
+        let to = -Quaternion::from_angle_y(Deg(50.0));
+        let half = slerp(from, to, 0.5);
+        assert!(angle_between(half, Quaternion::from_angle_y(Deg(30.0))) < 1e-4);
+        assert!(angle_between(slerp(from, to, 0.0), from) < 1e-4);
+        assert!(angle_between(slerp(from, to, 1.0), to) < 1e-4);
+    }
+
+    #[test]
+    fn look_rotation_follows_the_camera_convention() {
+        let rotation = look_rotation(Vector3::new(1.0, 0.0, 0.0), Vector3::unit_y()).unwrap();
+        assert!((rotation.rotate_vector(-Vector3::unit_z()) - Vector3::unit_x()).magnitude() < 1e-6);
+        assert!((rotation.rotate_vector(Vector3::unit_y()) - Vector3::unit_y()).magnitude() < 1e-6);
+        assert!((axes(rotation).x - Vector3::new(0.0, 0.0, 1.0)).magnitude() < 1e-6);
+        assert_eq!(look_rotation(Vector3::unit_y(), Vector3::unit_y()), None);
+
+        let drifted = Matrix3::from_cols(Vector3::new(1.01, 0.0, 0.0), Vector3::new(0.0, 0.99, 0.02), Vector3::unit_z());
+        assert!((from_axes(drifted).magnitude() - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,186 @@
+use std::cmp::Ordering;
+
+use cgmath::{InnerSpace, Matrix4, Vector3};
+
+/// Number of directional lights shaders receive at most; further ones are dropped, dimmest first.
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+
+/// Number of point lights shaders receive per drawn node at most; the ones reaching closest to the node are chosen.
+pub const MAX_POINT_LIGHTS: usize = 8;
+
+/// Defines sizing the light arrays of shaders, to be passed to
+/// [`ShaderProgram::load`](crate::shader::ShaderProgram::load).
+pub const LIGHT_DEFINES: [(&str, &str); 2] = [("MAX_DIRECTIONAL_LIGHTS", "4"), ("MAX_POINT_LIGHTS", "8")];
+
+/// The kind of a [`Light`] and what it depends on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightKind {
+    /// lights all surfaces evenly from every direction
+    Ambient,
+    /// parallel rays, such as sunlight, travelling along `direction` given in the space of the node
+    Directional { direction: Vector3<f32> },
+    /// shines from the origin of the node in all directions, fading out until `range`
+    Point { range: f32 },
+}
+
+/// A source of light attached to a node of a [`SceneGraph`](crate::scene::SceneGraph).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Light {
+    /// linear RGB color
+    pub color: [f32; 3],
+    /// factor the color is multiplied with
+    pub intensity: f32,
+    pub kind: LightKind,
+}
+
+impl Light {
+
+    #[must_use]
+    pub fn ambient(color: [f32; 3], intensity: f32) -> Self {
+        Self { color, intensity, kind: LightKind::Ambient }
+    }
+
+    #[must_use]
+    pub fn directional(direction: Vector3<f32>, color: [f32; 3], intensity: f32) -> Self {
+        Self { color, intensity, kind: LightKind::Directional { direction } }
+    }
+
+    #[must_use]
+    pub fn point(range: f32, color: [f32; 3], intensity: f32) -> Self {
+        Self { color, intensity, kind: LightKind::Point { range } }
+    }
+
+    fn radiance(&self) -> [f32; 3] {
+        let [r, g, b] = self.color;
+        [r * self.intensity, g * self.intensity, b * self.intensity]
+    }
+
+}
+
+pub(crate) struct DirectionalLight {
+    /// normalized, pointing towards the light
+    pub to_light: [f32; 3],
+    pub radiance: [f32; 3],
+}
+
+pub(crate) struct PointLight {
+    pub position: Vector3<f32>,
+    pub radiance: [f32; 3],
+    pub range: f32,
+}
+
+/// All lights of a frame in world space.
+#[derive(Default)]
+pub(crate) struct FrameLights {
+    pub ambient: [f32; 3],
+    pub directional: Vec<DirectionalLight>,
+    pub point: Vec<PointLight>,
+}
+
+impl FrameLights {
+
+    /// Collects the lights along with the world matrices of their nodes.
+    pub fn gather<'a, I>(lights: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a Light, &'a Matrix4<f32>)>
+    {
+        let mut frame = Self::default();
+        let mut directional = Vec::new();
+        for (light, matrix) in lights {
+            let radiance = light.radiance();
+            match light.kind {
+                LightKind::Ambient => {
+                    for (sum, value) in frame.ambient.iter_mut().zip(&radiance) {
+                        *sum += value;
+                    }
+                },
+                LightKind::Directional { direction } => {
+                    let to_light = -(matrix * direction.extend(0.0)).truncate().normalize();
+                    directional.push((light.intensity, DirectionalLight { to_light: to_light.into(), radiance }));
+                },
+                LightKind::Point { range } => {
+                    frame.point.push(PointLight { position: matrix.w.truncate(), radiance, range });
+                },
+            }
+        }
+        directional.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        frame.directional = directional.into_iter().take(MAX_DIRECTIONAL_LIGHTS).map(|(_, light)| light).collect();
+        frame
+    }
+
+    /// Chooses the point lights for a node at `position`: those whose range reaches closest to it.
+    ///
+    /// Only the origin of the node is known here, so a large mesh may miss a light touching its far end.
+    pub fn nearest_points(&self, position: Vector3<f32>) -> Vec<&PointLight> {
+        let gap = |light: &PointLight| (light.position - position).magnitude() - light.range;
+        let mut lights: Vec<(f32, &PointLight)> = self.point.iter().map(|light| (gap(light), light)).collect();
+        lights.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        lights.into_iter().take(MAX_POINT_LIGHTS).map(|(_, light)| light).collect()
+    }
+
+}
+
+/// Names of the elements of the uniform arrays of lights, e.g. `point_light_positions[3]`.
+pub(crate) struct LightUniformNames {
+    pub directional_directions: Vec<String>,
+    pub directional_radiances: Vec<String>,
+    pub point_positions: Vec<String>,
+    pub point_radiances: Vec<String>,
+    pub point_ranges: Vec<String>,
+}
+
+impl LightUniformNames {
+
+    pub fn new() -> Self {
+        let names = |name: &str, count| (0..count).map(|index| format!("{name}[{index}]")).collect();
+        Self {
+            directional_directions: names("directional_light_directions", MAX_DIRECTIONAL_LIGHTS),
+            directional_radiances: names("directional_light_radiances", MAX_DIRECTIONAL_LIGHTS),
+            point_positions: names("point_light_positions", MAX_POINT_LIGHTS),
+            point_radiances: names("point_light_radiances", MAX_POINT_LIGHTS),
+            point_ranges: names("point_light_ranges", MAX_POINT_LIGHTS),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, SquareMatrix};
+
+    #[test]
+    fn defines_match_limits() {
+        assert_eq!(LIGHT_DEFINES, [
+            ("MAX_DIRECTIONAL_LIGHTS", MAX_DIRECTIONAL_LIGHTS.to_string().as_str()),
+            ("MAX_POINT_LIGHTS", MAX_POINT_LIGHTS.to_string().as_str()),
+        ]);
+    }
+
+    #[test]
+    fn lights_are_gathered_in_world_space() {
+        let sun = Light::directional(Vector3::new(0.0, -1.0, 0.0), [1.0, 0.9, 0.8], 2.0);
+        let sky = Light::ambient([0.1, 0.1, 0.2], 1.0);
+        let lamps: Vec<Light> = (0_u8..10).map(|index| Light::point(1.0 + f32::from(index), [1.0; 3], 1.0)).collect();
+        let tilted = Matrix4::from_angle_z(Deg(90.0));
+        let lamp_matrices: Vec<Matrix4<f32>> = (0_u8..10).map(|index| {
+            Matrix4::from_translation(Vector3::new(0.0, 0.0, 3.0 * f32::from(index)))
+        }).collect();
+
+        let identity = Matrix4::identity();
+        let lights = vec![(&sun, &tilted), (&sky, &identity), (&sky, &identity)].into_iter()
+                .chain(lamps.iter().zip(&lamp_matrices));
+        let frame = FrameLights::gather(lights);
+        assert!((Vector3::from(frame.ambient) - Vector3::new(0.2, 0.2, 0.4)).magnitude() < 1e-6);
+        assert_eq!(frame.directional.len(), 1);
+        // the sun shines along -y rotated to +x, so the light is at -x
+        assert!((Vector3::from(frame.directional[0].to_light) - Vector3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-6);
+        assert!((Vector3::from(frame.directional[0].radiance) - Vector3::new(2.0, 1.8, 1.6)).magnitude() < 1e-6);
+
+        // lamp n is at z = 3n with range n + 1, so the ones further along reach closer to a node at the end
+        let chosen: Vec<f32> = frame.nearest_points(Vector3::new(0.0, 0.0, 27.0)).iter().map(|light| light.range).collect();
+        assert_eq!(chosen.len(), MAX_POINT_LIGHTS);
+        assert!((chosen[0] - 10.0).abs() < 1e-6);
+        assert!(!chosen.iter().any(|range| (range - 1.0).abs() < 1e-6));
+    }
+}
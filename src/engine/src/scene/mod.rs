@@ -0,0 +1,271 @@
+//! A hierarchy of objects placed in the world.
+//!
+//! A [`SceneGraph`] owns the GPU resources of meshes and textures along with materials and a tree of [`Node`]s. Each
+//! node is placed relative to its parent, so moving a ship moves its engine flames and cockpit along with it; the
+//! same goes for [`Light`]s attached to nodes, such as the glow of an engine. All
+//! parts are referenced by ids handed out when they are added; nothing is ever removed, but meshes may be replaced,
+//! e.g. after they have been reloaded.
+
+use cgmath::{Matrix, Matrix3, Matrix4, One, Quaternion, SquareMatrix, Vector3};
+
+use crate::model::MeshBuffers;
+use crate::texture::Texture;
+
+pub use light::{LIGHT_DEFINES, Light, LightKind, MAX_DIRECTIONAL_LIGHTS, MAX_POINT_LIGHTS};
+pub use render::Renderer;
+
+mod light;
+mod render;
+
+/// Identifies a [`Node`] within its [`SceneGraph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// Identifies a mesh within its [`SceneGraph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MeshId(usize);
+
+/// Identifies a [`Material`] within its [`SceneGraph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialId(usize);
+
+/// Identifies a texture within its [`SceneGraph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureId(usize);
+
+/// Placement of a node relative to its parent; scaling is applied first, translation last.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Transform {
+
+    #[must_use]
+    pub fn from_translation(translation: Vector3<f32>) -> Self {
+        Self { translation, ..Self::default() }
+    }
+
+    /// The matrix transforming points from the local space of the node into the space of its parent.
+    #[must_use]
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+                * Matrix4::from(self.rotation)
+                * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+}
+
+/// How the surface of a mesh looks, following the Blinn-Phong model.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Material {
+    /// linear RGBA color multiplied with the texture; reflects ambient and diffuse light
+    pub base_color: [f32; 4],
+    /// a white texture is used if there's none
+    pub texture: Option<TextureId>,
+    /// linear RGB color of highlights
+    pub specular: [f32; 3],
+    /// exponent of the specular term; higher values make smaller, sharper highlights
+    pub shininess: f32,
+    /// linear RGB color of light emitted by the surface itself, independent of any light
+    pub emissive: [f32; 3],
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self { base_color: [1.0; 4], texture: None, specular: [0.5; 3], shininess: 32.0, emissive: [0.0; 3] }
+    }
+}
+
+/// A mesh drawn with a material.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Drawable {
+    pub mesh: MeshId,
+    pub material: MaterialId,
+}
+
+/// An element of the transform tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub name: String,
+    /// placement relative to the parent node
+    pub transform: Transform,
+    /// what to draw at the node, if anything
+    pub drawable: Option<Drawable>,
+    /// light emitted from the node, if any
+    pub light: Option<Light>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Node {
+
+    #[must_use]
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    #[must_use]
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+}
+
+/// The objects of a scene along with their meshes, materials and textures.
+#[derive(Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SceneGraph {
+    /// parents always precede their children
+    nodes: Vec<Node>,
+    meshes: Vec<MeshBuffers>,
+    materials: Vec<Material>,
+    textures: Vec<Texture>,
+}
+
+impl SceneGraph {
+
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_mesh(&mut self, mesh: MeshBuffers) -> MeshId {
+        self.meshes.push(mesh);
+        MeshId(self.meshes.len() - 1)
+    }
+
+    /// Replaces the buffers of a mesh for all nodes drawing it.
+    pub fn replace_mesh(&mut self, id: MeshId, mesh: MeshBuffers) {
+        self.meshes[id.0] = mesh;
+    }
+
+    pub fn add_material(&mut self, material: Material) -> MaterialId {
+        self.materials.push(material);
+        MaterialId(self.materials.len() - 1)
+    }
+
+    pub fn add_texture(&mut self, texture: Texture) -> TextureId {
+        self.textures.push(texture);
+        TextureId(self.textures.len() - 1)
+    }
+
+    /// Adds a node as child of `parent` or as a root node.
+    pub fn add_node(&mut self, parent: Option<NodeId>, name: &str, transform: Transform, drawable: Option<Drawable>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node { name: name.to_string(), transform, drawable, light: None, parent, children: Vec::new() });
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        id
+    }
+
+    /// Adds a node emitting `light` as child of `parent` or as a root node.
+    pub fn add_light(&mut self, parent: Option<NodeId>, name: &str, transform: Transform, light: Light) -> NodeId {
+        let id = self.add_node(parent, name, transform, None);
+        self.nodes[id.0].light = Some(light);
+        id
+    }
+
+    #[must_use]
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    /// Returns the first node having the given name.
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|node| node.name == name).map(NodeId)
+    }
+
+    #[must_use]
+    pub fn material(&self, id: MaterialId) -> &Material {
+        &self.materials[id.0]
+    }
+
+    pub fn material_mut(&mut self, id: MaterialId) -> &mut Material {
+        &mut self.materials[id.0]
+    }
+
+    /// The matrix transforming points from the local space of a node into world space.
+    #[must_use]
+    pub fn world_matrix(&self, id: NodeId) -> Matrix4<f32> {
+        let node = self.node(id);
+        let parent = node.parent.map_or_else(Matrix4::identity, |parent| self.world_matrix(parent));
+        parent * node.transform.matrix()
+    }
+
+    /// Computes the world matrices of all nodes in a single pass, indexed like the nodes.
+    fn world_matrices(&self) -> Vec<Matrix4<f32>> {
+        let mut matrices: Vec<Matrix4<f32>> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let local = node.transform.matrix();
+            let matrix = node.parent.map_or(local, |parent| matrices[parent.0] * local);
+            matrices.push(matrix);
+        }
+        matrices
+    }
+
+}
+
+/// The matrix transforming normals along with points transformed by `model`: the inverse transpose of its upper 3×3
+/// part, which keeps normals perpendicular to their surfaces under non-uniform scaling.
+#[must_use]
+pub fn normal_matrix(model: &Matrix4<f32>) -> Matrix3<f32> {
+    let linear = Matrix3::from_cols(model.x.truncate(), model.y.truncate(), model.z.truncate());
+    linear.invert().map_or(linear, |inverse| inverse.transpose())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, InnerSpace, Rotation3, Transform as _, Point3};
+
+    #[test]
+    fn children_follow_their_parents() {
+        let mut graph = SceneGraph::new();
+        let ship = graph.add_node(None, "ship", Transform::from_translation(Vector3::new(10.0, 0.0, 0.0)), None);
+        let engine = graph.add_node(Some(ship), "engine", Transform {
+            translation: Vector3::new(0.0, 0.0, 2.0),
+            scale: Vector3::new(2.0, 2.0, 2.0),
+            ..Transform::default()
+        }, None);
+        let flame = graph.add_node(Some(engine), "flame", Transform::from_translation(Vector3::new(0.0, 0.0, 1.0)), None);
+        assert_eq!(graph.node(ship).children(), [engine]);
+        assert_eq!(graph.find("flame"), Some(flame));
+
+        let point = |graph: &SceneGraph, id| graph.world_matrix(id).transform_point(Point3::new(0.0, 0.0, 0.0));
+        assert!((point(&graph, flame) - Point3::new(10.0, 0.0, 4.0)).magnitude() < 1e-6);
+
+        graph.node_mut(ship).transform.rotation = Quaternion::from_angle_y(Deg(90.0));
+        let matrices = graph.world_matrices();
+        assert!((point(&graph, flame) - Point3::new(14.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert_eq!(matrices[flame.0], graph.world_matrix(flame));
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_scaling() {
+        let model = Matrix4::from_nonuniform_scale(4.0, 1.0, 1.0);
+        // a surface sloped by 45° in the x-y plane
+        let (tangent, normal) = (Vector3::new(1.0, 1.0, 0.0), Vector3::new(1.0, -1.0, 0.0));
+        let tangent = model.transform_vector(tangent);
+        let normal = normal_matrix(&model) * normal;
+        assert!(tangent.dot(normal).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,142 @@
+use cgmath::{Matrix4, SquareMatrix};
+use glium::backend::Facade;
+use glium::uniforms::{AsUniformValue, UniformValue, Uniforms};
+use glium::{DrawError, Program, Surface};
+
+use crate::GliumMatrix;
+use crate::scene::light::{FrameLights, LightUniformNames, PointLight};
+use crate::scene::{Material, SceneGraph, normal_matrix};
+use crate::texture::{self, Sampled, Texture, TextureError};
+use crate::viewport::Viewport;
+
+/// Draws [`SceneGraph`]s node by node.
+///
+/// Besides the matrices of the camera (`persp_matrix` and `view_matrix`) and its `camera_position` in world space,
+/// the program receives for each node
+///
+/// * its `model_matrix` (local to world space) and `normal_matrix` (a `mat3` transforming normals into world space),
+/// * the `base_color`, `diffuse_texture`, `specular`, `shininess` and `emissive` color of its material,
+/// * the sum of all ambient lights as `ambient_light`,
+/// * `directional_light_count` directional lights in the arrays `directional_light_directions` (pointing towards the
+///   light) and `directional_light_radiances`, and
+/// * `point_light_count` of the point lights closest to it in `point_light_positions`, `point_light_radiances` and
+///   `point_light_ranges`.
+///
+/// Radiances are light colors multiplied with their intensities. The arrays are sized by
+/// [`LIGHT_DEFINES`](crate::scene::LIGHT_DEFINES); `lighting.glsl` among the game's shaders shows how to use them.
+pub struct Renderer {
+    /// used for materials without a texture
+    white: Texture,
+    anisotropy: u16,
+    names: LightUniformNames,
+}
+
+impl Renderer {
+
+    /// Creates a renderer sampling textures with up to `anisotropy` samples, limited to what the context supports.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the default texture cannot be created.
+    pub fn new<F: Facade + ?Sized>(facade: &F, anisotropy: u16) -> Result<Self, TextureError> {
+        Ok(Self {
+            white: Texture::white(facade)?,
+            anisotropy: texture::anisotropy(facade.get_context(), anisotropy),
+            names: LightUniformNames::new(),
+        })
+    }
+
+    /// Draws all nodes having a [`Drawable`](crate::scene::Drawable) with depth testing, lit by all lights of the
+    /// scene, into `viewport` if given or else the whole target.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the program doesn't match the uniforms or vertex attributes.
+    pub fn draw<S: Surface>(&self, target: &mut S, scene: &SceneGraph, program: &Program, persp_matrix: GliumMatrix,
+            view_matrix: GliumMatrix, viewport: Option<Viewport>) -> Result<(), DrawError> {
+        let params = glium::DrawParameters {
+            depth: glium::Depth {
+                test: glium::DepthTest::IfLess,
+                write: true,
+                .. glium::Depth::default()
+            },
+            viewport: viewport.map(|viewport| viewport.rect()),
+            .. glium::DrawParameters::default()
+        };
+
+        let world_matrices = scene.world_matrices();
+        let lights = FrameLights::gather(scene.nodes.iter().zip(&world_matrices)
+                .filter_map(|(node, matrix)| node.light.as_ref().map(|light| (light, matrix))));
+        let camera_position = Matrix4::from(view_matrix).invert().map_or([0.0; 3], |matrix| matrix.w.truncate().into());
+
+        for (node, model_matrix) in scene.nodes.iter().zip(&world_matrices) {
+            let Some(drawable) = node.drawable else { continue };
+            let mesh = &scene.meshes[drawable.mesh.0];
+            let material = &scene.materials[drawable.material.0];
+            let texture = material.texture.map_or(&self.white, |texture| &scene.textures[texture.0]);
+
+            let uniforms = NodeUniforms {
+                persp_matrix,
+                view_matrix,
+                camera_position,
+                model_matrix: (*model_matrix).into(),
+                normal_matrix: normal_matrix(model_matrix).into(),
+                material,
+                texture: texture.sampled(self.anisotropy),
+                lights: &lights,
+                point_lights: lights.nearest_points(model_matrix.w.truncate()),
+                names: &self.names,
+            };
+            target.draw(&mesh.vertices, &mesh.indices, program, &uniforms, &params)?;
+        }
+        Ok(())
+    }
+
+}
+
+/// Everything the program receives for drawing a single node.
+struct NodeUniforms<'a> {
+    persp_matrix: GliumMatrix,
+    view_matrix: GliumMatrix,
+    camera_position: [f32; 3],
+    model_matrix: GliumMatrix,
+    normal_matrix: [[f32; 3]; 3],
+    material: &'a Material,
+    texture: Sampled<'a>,
+    lights: &'a FrameLights,
+    point_lights: Vec<&'a PointLight>,
+    names: &'a LightUniformNames,
+}
+
+impl Uniforms for NodeUniforms<'_> {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("persp_matrix", UniformValue::Mat4(self.persp_matrix));
+        visit("view_matrix", UniformValue::Mat4(self.view_matrix));
+        visit("camera_position", UniformValue::Vec3(self.camera_position));
+        visit("model_matrix", UniformValue::Mat4(self.model_matrix));
+        visit("normal_matrix", UniformValue::Mat3(self.normal_matrix));
+
+        let material = self.material;
+        visit("base_color", UniformValue::Vec4(material.base_color));
+        visit("diffuse_texture", self.texture.as_uniform_value());
+        visit("specular", UniformValue::Vec3(material.specular));
+        visit("shininess", UniformValue::Float(material.shininess));
+        visit("emissive", UniformValue::Vec3(material.emissive));
+
+        // the counts are limited by the lengths of the name arrays
+        let names = self.names;
+        visit("ambient_light", UniformValue::Vec3(self.lights.ambient));
+        visit("directional_light_count", UniformValue::SignedInt(self.lights.directional.len() as i32));
+        for (index, light) in self.lights.directional.iter().enumerate() {
+            visit(&names.directional_directions[index], UniformValue::Vec3(light.to_light));
+            visit(&names.directional_radiances[index], UniformValue::Vec3(light.radiance));
+        }
+        visit("point_light_count", UniformValue::SignedInt(self.point_lights.len() as i32));
+        for (index, light) in self.point_lights.iter().enumerate() {
+            visit(&names.point_positions[index], UniformValue::Vec3(light.position.into()));
+            visit(&names.point_radiances[index], UniformValue::Vec3(light.radiance));
+            visit(&names.point_ranges[index], UniformValue::Float(light.range));
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::{error, fmt, fs, io};
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::{ChaseSettings, EffectSettings};
+use crate::input::InputSource;
+use crate::input::gamepad::GamepadSettings;
+use crate::input::mouse::MouseSettings;
+use crate::input::response::ResponseSettings;
+use crate::logging::LogSettings;
+use crate::viewport::{MAX_PLAYERS, SplitLayout};
+
+/// Name of the settings file within the configuration directory
+pub const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+/// Everything that went wrong while reading, validating or writing the settings.
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The settings file could not be read or written
+    Io(io::Error),
+    /// The settings file is not valid TOML or doesn't match the expected structure
+    Parse(toml::de::Error),
+    /// The settings could not be converted into TOML
+    Serialize(toml::ser::Error),
+    /// The value of `key` is out of range
+    Invalid { key: String, reason: String },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Parse(err) => write!(f, "{err}"),
+            Self::Serialize(err) => write!(f, "{err}"),
+            Self::Invalid { key, reason } => write!(f, "invalid value for key `{key}`: {reason}"),
+        }
+    }
+}
+
+impl error::Error for SettingsError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Serialize(err) => Some(err),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for SettingsError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for SettingsError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<toml::ser::Error> for SettingsError {
+    fn from(err: toml::ser::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+/// All user-configurable settings of the engine.
+///
+/// Missing keys are filled with their defaults so a settings file only needs to contain the deviating values.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub window: WindowSettings,
+    pub graphics: GraphicsSettings,
+    pub input: InputSettings,
+    pub camera: CameraSettings,
+    pub players: PlayerSettings,
+    pub log: LogSettings,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowSettings {
+    /// inner width of the window in physical pixels
+    pub width: u32,
+    /// inner height of the window in physical pixels
+    pub height: u32,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 1024,
+            height: 768,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GraphicsSettings {
+    /// whether to synchronize buffer swaps with the monitor's refresh rate
+    pub vsync: bool,
+    /// number of samples per pixel; 0 disables multisampling
+    pub multisampling: u16,
+    /// number of bits to use for the depth buffer
+    pub depth_buffer_bits: u8,
+    /// vertical field of view in degrees
+    pub fov: f32,
+    /// distance of the near clipping plane
+    pub near: f32,
+    /// distance of the far clipping plane
+    pub far: f32,
+    /// degree of anisotropic texture filtering; 1 disables it, higher values are limited to what is supported
+    pub anisotropy: u16,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            vsync: true,
+            multisampling: 0,
+            depth_buffer_bits: 24, // TODO this is a wild guess; should rather be based on some facts
+            fov: 90.0,
+            near: 0.1,
+            far: 1024.0,
+            anisotropy: 16,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputSettings {
+    /// how fast (units per second) the control axes follow their input
+    pub gradient_speed: f32,
+    pub gamepad: GamepadSettings,
+    pub mouse: MouseSettings,
+    /// how each control axis follows its input and maps it onto its output
+    pub response: ResponseSettings,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            gradient_speed: 1.0,
+            gamepad: GamepadSettings::default(),
+            mouse: MouseSettings::default(),
+            response: ResponseSettings::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraSettings {
+    /// seconds taken to blend over to another camera; 0.0 cuts immediately
+    pub blend_time: f32,
+    /// how the third-person camera follows the ship
+    pub chase: ChaseSettings,
+    /// how the view conveys speed, turns and hits
+    pub effects: EffectSettings,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            blend_time: 0.5,
+            chase: ChaseSettings::default(),
+            effects: EffectSettings::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerSettings {
+    /// number of players sharing the screen
+    pub count: usize,
+    /// how the window is divided among the players
+    pub layout: SplitLayout,
+    /// the devices of each player in order; a single player uses all devices at once
+    pub inputs: Vec<InputSource>,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            layout: SplitLayout::Horizontal,
+            inputs: vec![InputSource::Keyboard, InputSource::Gamepad1, InputSource::Gamepad2, InputSource::Gamepad3],
+        }
+    }
+}
+
+impl Settings {
+
+    /// Reads the settings from the TOML file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read, cannot be parsed or contains invalid values.
+    pub fn load(path: &Path) -> Result<Self, SettingsError> {
+        debug!("load settings from {}", path.display());
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the settings from the TOML file at `path` or returns the defaults if there's no such file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file exists but cannot be read, cannot be parsed or contains invalid values.
+    pub fn load_or_default(path: &Path) -> Result<Self, SettingsError> {
+        match Self::load(path) {
+            Err(SettingsError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("no settings file found; using defaults");
+                Ok(Self::default())
+            },
+            result => result,
+        }
+    }
+
+    /// Parses and validates settings given in TOML format.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `toml` cannot be parsed or contains invalid values.
+    pub fn from_toml(toml: &str) -> Result<Self, SettingsError> {
+        let settings: Self = toml::from_str(toml)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Writes the settings into the TOML file at `path`, creating the parent directories if required.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the settings cannot be serialized or the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), SettingsError> {
+        debug!("save settings to {}", path.display());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Checks all values for being in their valid ranges.
+    ///
+    /// # Errors
+    ///
+    /// Reports the first key having an invalid value.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let window = &self.window;
+        check(window.width > 0, "window.width", "must be greater than 0")?;
+        check(window.height > 0, "window.height", "must be greater than 0")?;
+
+        let graphics = &self.graphics;
+        check(graphics.multisampling <= 16 && (graphics.multisampling == 0 || graphics.multisampling.is_power_of_two()),
+                "graphics.multisampling", "must be 0, 2, 4, 8 or 16")?;
+        check([16, 24, 32].contains(&graphics.depth_buffer_bits), "graphics.depth_buffer_bits", "must be 16, 24 or 32")?;
+        check(graphics.fov > 0.0 && graphics.fov < 180.0, "graphics.fov", "must be between 0 and 180 degrees")?;
+        check(graphics.near > 0.0 && graphics.near.is_finite(), "graphics.near", "must be greater than 0")?;
+        check(graphics.far > graphics.near && graphics.far.is_finite(), "graphics.far", "must be greater than graphics.near")?;
+        check((1..=16).contains(&graphics.anisotropy), "graphics.anisotropy", "must be between 1 and 16")?;
+
+        let input = &self.input;
+        check(input.gradient_speed > 0.0 && input.gradient_speed.is_finite(), "input.gradient_speed", "must be greater than 0")?;
+        if let Some(axis) = input.gamepad.invalid_mapping() {
+            return Err(SettingsError::Invalid {
+                key: format!("input.gamepad.{axis}"),
+                reason: "deadzone must be in 0.0..1.0 and sensitivity finite".to_string(),
+            });
+        }
+        check(input.mouse.sensitivity.is_finite(), "input.mouse.sensitivity", "must be a finite number")?;
+        check((0.0..1.0).contains(&input.mouse.smoothing), "input.mouse.smoothing", "must be in 0.0..1.0")?;
+        if let Some(axis) = input.response.invalid_axis() {
+            return Err(SettingsError::Invalid {
+                key: format!("input.response.{axis}"),
+                reason: "rates and exponent must be greater than 0, expo in 0.0..=1.0 and deadzone in 0.0..1.0".to_string(),
+            });
+        }
+
+        let blend_time = self.camera.blend_time;
+        check(blend_time >= 0.0 && blend_time.is_finite(), "camera.blend_time", "must be a finite number not less than 0")?;
+        if let Some(key) = self.camera.chase.invalid_key() {
+            return Err(SettingsError::Invalid {
+                key: format!("camera.chase.{key}"),
+                reason: "must be finite; distances and lags must not be negative".to_string(),
+            });
+        }
+        if let Some(key) = self.camera.effects.invalid_key() {
+            return Err(SettingsError::Invalid {
+                key: format!("camera.effects.{key}"),
+                reason: "must be finite and not negative; fov_max_speed must exceed fov_min_speed".to_string(),
+            });
+        }
+
+        let players = &self.players;
+        check((1..=MAX_PLAYERS).contains(&players.count), "players.count", "must be in 1..=4")?;
+        let inputs = players.inputs.get(..players.count).unwrap_or(&players.inputs);
+        let unique = inputs.iter().enumerate().all(|(index, input)| !inputs[..index].contains(input));
+        check(players.count == 1 || (inputs.len() == players.count && unique), "players.inputs",
+                "must name a different device for each player")?;
+
+        let log = &self.log;
+        check(log.max_file_size >= 1024, "log.max_file_size", "must be at least 1024 bytes")?;
+        check(log.max_files > 0, "log.max_files", "must be greater than 0")?;
+
+        Ok(())
+    }
+
+}
+
+fn check(valid: bool, key: &str, reason: &str) -> Result<(), SettingsError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(SettingsError::Invalid { key: key.to_string(), reason: reason.to_string() })
+    }
+}
+
+/// Returns the platform specific configuration directory of `application` (e.g. `~/.config/<application>` on Linux).
+#[must_use]
+pub fn config_dir(application: &str) -> Option<PathBuf> {
+    ProjectDirs::from("", "", application).map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+/// Returns the path of the settings file of `application` within its configuration directory.
+#[must_use]
+pub fn default_path(application: &str) -> Option<PathBuf> {
+    config_dir(application).map(|dir| dir.join(SETTINGS_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn missing_keys_use_defaults() {
+        let settings = Settings::from_toml("[window]\nwidth = 640\n").unwrap();
+        assert_eq!(settings.window.width, 640);
+        assert_eq!(settings.window.height, WindowSettings::default().height);
+        assert_eq!(settings.graphics, GraphicsSettings::default());
+    }
+
+    #[test]
+    fn invalid_values_name_their_key() {
+        match Settings::from_toml("[graphics]\nnear = 10.0\nfar = 5.0\n") {
+            Err(SettingsError::Invalid { key, .. }) => assert_eq!(key, "graphics.far"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn players_need_distinct_inputs() {
+        match Settings::from_toml("[players]\ncount = 2\ninputs = [\"gamepad1\", \"gamepad1\"]\n") {
+            Err(SettingsError::Invalid { key, .. }) => assert_eq!(key, "players.inputs"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let settings = Settings::from_toml("[players]\ncount = 2\nlayout = \"vertical\"\ninputs = [\"gamepad2\", \"keyboard\"]\n").unwrap();
+        assert_eq!(settings.players.inputs[1], InputSource::Keyboard);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut settings = Settings::default();
+        settings.graphics.vsync = false;
+        settings.log.modules.insert("reactor_ls_engine::input".to_string(), log::LevelFilter::Trace);
+        let toml = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(Settings::from_toml(&toml).unwrap(), settings);
+    }
+}
@@ -0,0 +1,416 @@
+//! Shader sources that are compiled for whichever GLSL version the OpenGL context supports.
+//!
+//! Shaders are written once without a `#version` directive in the common subset of GLSL 1.40 and GLSL ES 3.00. The
+//! preprocessor resolves `#include "file"` directives, prepends the directive of a [`GlslTarget`] together with
+//! `#define`s given by the application, and adds what the target requires on top, such as default precisions for
+//! GLSL ES. Everything else, including `#define`s within the sources, is left to the GLSL compiler.
+//!
+//! Besides the defines of the application, every variant defines `GLSL_VERSION` (e.g. `330`) and one of `GLSL_140`,
+//! `GLSL_330`, `GLSL_300_ES` or `GLSL_430`, so sources can use newer features where available.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::{error, fmt};
+
+use glium::backend::{Context, Facade};
+use glium::program::ProgramCreationError;
+use glium::{Api, Program, Version};
+use log::debug;
+
+use crate::assets::{AssetError, Assets, Handle, relative_path};
+
+/// Everything that went wrong while preprocessing or compiling shaders.
+#[derive(Debug)]
+pub enum ShaderError {
+    /// A source file or one of its includes could not be loaded
+    Asset(AssetError),
+    /// The `#include` directive in `line` of `file` doesn't name a file in quotes or angle brackets
+    InvalidInclude { file: String, line: usize },
+    /// The name of a define given by the application is not a valid identifier
+    InvalidDefine(String),
+    /// The context supports none of the GLSL versions shaders are generated for
+    UnsupportedVersion(Version),
+    /// The preprocessed sources failed to compile or link; source string numbers in the compiler's messages refer
+    /// to the files of the respective stage
+    Compile { vertex_files: Vec<String>, fragment_files: Vec<String>, error: ProgramCreationError },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Asset(err) => write!(f, "{err}"),
+            Self::InvalidInclude { file, line } => write!(f, "invalid #include in line {line} of `{file}`"),
+            Self::InvalidDefine(name) => write!(f, "invalid name of define `{name}`"),
+            Self::UnsupportedVersion(version) => write!(f, "GLSL version {version:?} is not supported"),
+            Self::Compile { vertex_files, fragment_files, error } => {
+                let describe = |files: &[String]| {
+                    files.iter().enumerate().map(|(index, file)| format!("{index}: {file}")).collect::<Vec<_>>().join(", ")
+                };
+                write!(f, "{error} (vertex shader files {}; fragment shader files {})", describe(vertex_files), describe(fragment_files))
+            },
+        }
+    }
+}
+
+impl error::Error for ShaderError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Asset(err) => Some(err),
+            Self::Compile { error, .. } => Some(error),
+            Self::InvalidInclude { .. } | Self::InvalidDefine(_) | Self::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<AssetError> for ShaderError {
+    fn from(err: AssetError) -> Self {
+        Self::Asset(err)
+    }
+}
+
+/// A GLSL version shaders are generated for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GlslTarget {
+    /// OpenGL 3.1, the oldest version supported
+    Glsl140,
+    /// OpenGL 3.3 core profile
+    Glsl330,
+    /// OpenGL ES 3.0 and WebGL 2.0
+    Glsl300Es,
+    /// OpenGL 4.3 core profile
+    Glsl430,
+}
+
+impl GlslTarget {
+
+    /// All targets, most restricted first.
+    pub const ALL: [Self; 4] = [Self::Glsl300Es, Self::Glsl140, Self::Glsl330, Self::Glsl430];
+
+    /// Returns the newest target the context supports, based on its `get_supported_glsl_version`.
+    #[must_use]
+    pub fn select(context: &Context) -> Option<Self> {
+        Self::for_version(context.get_supported_glsl_version())
+    }
+
+    /// Returns the newest target that can be compiled by an implementation of the given GLSL version.
+    #[must_use]
+    pub fn for_version(Version(api, major, minor): Version) -> Option<Self> {
+        let version = (major, minor);
+        match api {
+            Api::Gl if version >= (4, 3) => Some(Self::Glsl430),
+            Api::Gl if version >= (3, 3) => Some(Self::Glsl330),
+            Api::Gl if version >= (1, 4) => Some(Self::Glsl140),
+            Api::GlEs if version >= (3, 0) => Some(Self::Glsl300Es),
+            _ => None,
+        }
+    }
+
+    /// The `#version` directive starting each source.
+    #[must_use]
+    pub fn directive(self) -> &'static str {
+        match self {
+            Self::Glsl140 => "#version 140",
+            Self::Glsl330 => "#version 330 core",
+            Self::Glsl300Es => "#version 300 es",
+            Self::Glsl430 => "#version 430 core",
+        }
+    }
+
+    /// Name of the define identifying the target.
+    fn define(self) -> &'static str {
+        match self {
+            Self::Glsl140 => "GLSL_140",
+            Self::Glsl330 => "GLSL_330",
+            Self::Glsl300Es => "GLSL_300_ES",
+            Self::Glsl430 => "GLSL_430",
+        }
+    }
+
+    fn number(self) -> u16 {
+        match self {
+            Self::Glsl140 => 140,
+            Self::Glsl330 => 330,
+            Self::Glsl300Es => 300,
+            Self::Glsl430 => 430,
+        }
+    }
+
+    /// A `#line` directive making the following line `line` of source string `file`.
+    fn line_directive(self, line: usize, file: usize) -> String {
+        // before GLSL 3.30 the number given referred to the directive itself
+        let line = if self == Self::Glsl140 { line - 1 } else { line };
+        format!("#line {line} {file}")
+    }
+
+}
+
+/// The source of a single shader stage generated for a [`GlslTarget`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderSource {
+    source: String,
+    files: Vec<String>,
+}
+
+impl ShaderSource {
+
+    /// The complete source to hand over to the compiler.
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Logical paths of the file and all its includes, indexed by the source string numbers of `#line` directives.
+    #[must_use]
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+}
+
+/// Generates the source of the shader at the logical `path` for `target`.
+///
+/// `load` returns the content of a file by its logical path; paths of includes are relative to the including file.
+/// Each file is included only once, so include guards aren't required and cyclic includes are harmless.
+///
+/// # Errors
+///
+/// Fails if a define has an invalid name, an `#include` directive is malformed or `load` fails.
+pub fn preprocess<F>(path: &str, target: GlslTarget, defines: &[(&str, &str)], mut load: F) -> Result<ShaderSource, ShaderError>
+where
+    F: FnMut(&str) -> Result<String, AssetError>
+{
+    if let Some((name, _)) = defines.iter().find(|(name, _)| !is_identifier(name)) {
+        return Err(ShaderError::InvalidDefine((*name).to_string()));
+    }
+    // GLSL ES has no default precision for floats in fragment shaders
+    let precision = if target == GlslTarget::Glsl300Es { "precision highp float;\nprecision highp int;\n" } else { "" };
+    let mut source = format!("{}\n{precision}#define {} 1\n#define GLSL_VERSION {}\n",
+            target.directive(), target.define(), target.number());
+    for (name, value) in defines {
+        // writing into a string cannot fail
+        let _ = writeln!(source, "#define {name} {value}");
+    }
+
+    let mut files = vec![path.to_string()];
+    let mut included = HashSet::new();
+    included.insert(path.to_string());
+    source.push_str(&target.line_directive(1, 0));
+    source.push('\n');
+    let content = load(path)?;
+    append(&mut source, &mut files, &mut included, 0, &content, target, &mut load)?;
+    Ok(ShaderSource { source, files })
+}
+
+/// Appends `content` of `files[file]`, replacing its `#version` directive by an empty line and its `#include`
+/// directives by the content of the included files.
+fn append<F>(
+    source: &mut String,
+    files: &mut Vec<String>,
+    included: &mut HashSet<String>,
+    file: usize,
+    content: &str,
+    target: GlslTarget,
+    load: &mut F,
+) -> Result<(), ShaderError>
+where
+    F: FnMut(&str) -> Result<String, AssetError>
+{
+    for (index, line) in content.lines().enumerate() {
+        let directive = line.trim_start().strip_prefix('#').map(str::trim_start);
+        if directive.is_some_and(|directive| directive.starts_with("version")) {
+            source.push('\n');
+            continue;
+        }
+        let Some(argument) = directive.and_then(|directive| directive.strip_prefix("include")) else {
+            source.push_str(line);
+            source.push('\n');
+            continue;
+        };
+
+        let argument = argument.trim();
+        let name = argument.strip_prefix('"').and_then(|name| name.strip_suffix('"'))
+                .or_else(|| argument.strip_prefix('<').and_then(|name| name.strip_suffix('>')))
+                .ok_or_else(|| ShaderError::InvalidInclude { file: files[file].clone(), line: index + 1 })?;
+        let path = relative_path(&files[file], name);
+        if included.insert(path.clone()) {
+            debug!("include {path} into {}", files[0]);
+            let content = load(&path)?;
+            files.push(path);
+            let include = files.len() - 1;
+            source.push_str(&target.line_directive(1, include));
+            source.push('\n');
+            append(source, files, included, include, &content, target, load)?;
+        }
+        source.push_str(&target.line_directive(index + 2, file));
+        source.push('\n');
+    }
+    Ok(())
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A shader program whose sources are loaded from [`Assets`] and preprocessed for the GLSL version of the context.
+pub struct ShaderProgram {
+    program: Program,
+    target: GlslTarget,
+    vertex_path: String,
+    fragment_path: String,
+    defines: Vec<(String, String)>,
+    /// all files the program has been built from with the versions they had
+    sources: Vec<(Handle<String>, u32)>,
+}
+
+impl ShaderProgram {
+
+    /// Loads the vertex and fragment shaders at the given logical paths and compiles them into a program.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the context doesn't support any [`GlslTarget`], preprocessing fails or the shaders don't compile.
+    pub fn load<F: Facade + ?Sized>(
+        facade: &F,
+        assets: &mut Assets,
+        vertex_path: &str,
+        fragment_path: &str,
+        defines: &[(&str, &str)],
+    ) -> Result<Self, ShaderError> {
+        let context = facade.get_context();
+        let target = GlslTarget::select(context)
+                .ok_or_else(|| ShaderError::UnsupportedVersion(context.get_supported_glsl_version()))?;
+        let mut sources = Vec::new();
+        let program = build(facade, assets, target, vertex_path, fragment_path, defines, &mut sources)?;
+        Ok(Self {
+            program,
+            target,
+            vertex_path: vertex_path.to_string(),
+            fragment_path: fragment_path.to_string(),
+            defines: defines.iter().map(|(name, value)| ((*name).to_string(), (*value).to_string())).collect(),
+            sources: versioned(sources),
+        })
+    }
+
+    #[must_use]
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// The GLSL version the sources have been generated for.
+    #[must_use]
+    pub fn target(&self) -> GlslTarget {
+        self.target
+    }
+
+    /// Compiles the program again if any of its source files has been reloaded by [`Assets::reload_changed`] and
+    /// returns whether it has been replaced.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the changed sources don't compile; the previous program is kept in that case.
+    pub fn reload<F: Facade + ?Sized>(&mut self, facade: &F, assets: &mut Assets) -> Result<bool, ShaderError> {
+        if self.sources.iter().all(|(handle, version)| handle.version() == *version) {
+            return Ok(false);
+        }
+        debug!("recompile shaders {} and {}", self.vertex_path, self.fragment_path);
+        let defines: Vec<(&str, &str)> = self.defines.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+        let mut sources = Vec::new();
+        let result = build(facade, assets, self.target, &self.vertex_path, &self.fragment_path, &defines, &mut sources);
+        // broken sources shall not be compiled again before they change
+        self.sources = versioned(sources);
+        self.program = result?;
+        Ok(true)
+    }
+
+}
+
+fn versioned(handles: Vec<Handle<String>>) -> Vec<(Handle<String>, u32)> {
+    handles.into_iter().map(|handle| {
+        let version = handle.version();
+        (handle, version)
+    }).collect()
+}
+
+/// Preprocesses and compiles both stages, collecting the handles of all files that have been read into `sources`.
+fn build<F: Facade + ?Sized>(
+    facade: &F,
+    assets: &mut Assets,
+    target: GlslTarget,
+    vertex_path: &str,
+    fragment_path: &str,
+    defines: &[(&str, &str)],
+    sources: &mut Vec<Handle<String>>,
+) -> Result<Program, ShaderError> {
+    let mut load = |path: &str| {
+        let handle = assets.load::<String>(path)?;
+        let content = handle.get().clone();
+        sources.push(handle);
+        Ok(content)
+    };
+    let vertex = preprocess(vertex_path, target, defines, &mut load)?;
+    let fragment = preprocess(fragment_path, target, defines, &mut load)?;
+    Program::from_source(facade, vertex.source(), fragment.source(), None).map_err(|error| {
+        ShaderError::Compile { vertex_files: vertex.files, fragment_files: fragment.files, error }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn preprocess_all(target: GlslTarget, files: &HashMap<&str, &str>) -> Result<ShaderSource, ShaderError> {
+        preprocess("shaders/main.glsl", target, &[("LIGHTS", "4")], |path| {
+            files.get(path).map(|content| (*content).to_string())
+                    .ok_or_else(|| AssetError::NotFound { path: path.to_string(), search_path: Vec::new() })
+        })
+    }
+
+    #[test]
+    fn includes_are_resolved_once() {
+        let files: HashMap<&str, &str> = vec![
+            ("shaders/main.glsl", "#version 140\n#include \"lib/light.glsl\"\n  # include <common.glsl>\nvoid main() {}"),
+            ("shaders/lib/light.glsl", "#include \"../common.glsl\"\nvec3 light();"),
+            ("shaders/common.glsl", "#include \"main.glsl\"\nconst float PI = 3.14159;"),
+        ].into_iter().collect();
+
+        let shader = preprocess_all(GlslTarget::Glsl330, &files).unwrap();
+        assert_eq!(shader.files(), ["shaders/main.glsl", "shaders/lib/light.glsl", "shaders/common.glsl"]);
+        assert_eq!(shader.source(), "#version 330 core\n#define GLSL_330 1\n#define GLSL_VERSION 330\n#define LIGHTS 4\n\
+                #line 1 0\n\n\
+                #line 1 1\n#line 1 2\n#line 2 2\nconst float PI = 3.14159;\n#line 2 1\nvec3 light();\n#line 3 0\n\
+                #line 4 0\nvoid main() {}\n");
+
+        let missing: HashMap<&str, &str> = vec![("shaders/main.glsl", "#include \"missing.glsl\"")].into_iter().collect();
+        assert!(matches!(preprocess_all(GlslTarget::Glsl330, &missing), Err(ShaderError::Asset(AssetError::NotFound { path, .. })) if path == "shaders/missing.glsl"));
+        let invalid: HashMap<&str, &str> = vec![("shaders/main.glsl", "\n#include missing.glsl")].into_iter().collect();
+        assert!(matches!(preprocess_all(GlslTarget::Glsl330, &invalid), Err(ShaderError::InvalidInclude { line: 2, .. })));
+    }
+
+    #[test]
+    fn targets_differ_in_their_header() {
+        let files: HashMap<&str, &str> = vec![("shaders/main.glsl", "void main() {}")].into_iter().collect();
+        let headers: Vec<String> = GlslTarget::ALL.iter()
+                .map(|target| preprocess_all(*target, &files).unwrap().source().lines().take(3).collect::<Vec<_>>().join("\n"))
+                .collect();
+        assert_eq!(headers, [
+            "#version 300 es\nprecision highp float;\nprecision highp int;",
+            "#version 140\n#define GLSL_140 1\n#define GLSL_VERSION 140",
+            "#version 330 core\n#define GLSL_330 1\n#define GLSL_VERSION 330",
+            "#version 430 core\n#define GLSL_430 1\n#define GLSL_VERSION 430",
+        ]);
+        assert!(preprocess_all(GlslTarget::Glsl140, &files).unwrap().source().contains("#line 0 0\nvoid main() {}"));
+
+        assert!(matches!(preprocess("main.glsl", GlslTarget::Glsl140, &[("1A", "")], |_| Ok(String::new())), Err(ShaderError::InvalidDefine(_))));
+    }
+
+    #[test]
+    fn targets_are_selected_by_glsl_version() {
+        assert_eq!(GlslTarget::for_version(Version(Api::Gl, 1, 4)), Some(GlslTarget::Glsl140));
+        assert_eq!(GlslTarget::for_version(Version(Api::Gl, 4, 1)), Some(GlslTarget::Glsl330));
+        assert_eq!(GlslTarget::for_version(Version(Api::Gl, 4, 6)), Some(GlslTarget::Glsl430));
+        assert_eq!(GlslTarget::for_version(Version(Api::GlEs, 3, 2)), Some(GlslTarget::Glsl300Es));
+        assert_eq!(GlslTarget::for_version(Version(Api::GlEs, 1, 0)), None);
+        assert_eq!(GlslTarget::for_version(Version(Api::Gl, 1, 3)), None);
+    }
+}
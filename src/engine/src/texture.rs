@@ -0,0 +1,523 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+use glium::backend::{Context, Facade};
+use glium::texture::{
+    ClientFormat, CompressedFormat, CompressedMipmapsOption, CompressedSrgbFormat, CompressedSrgbTexture2d, CompressedTexture2d,
+    MipmapsOption, RawImage2d, SrgbTexture2d, Texture2d, TextureCreationError,
+};
+use glium::uniforms::{
+    AsUniformValue, MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior, SamplerWrapFunction, UniformValue,
+};
+use glium::Rect;
+use log::debug;
+
+/// Everything that went wrong while loading a texture.
+#[derive(Debug)]
+pub enum TextureError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The PNG or JPEG image could not be decoded
+    Decode(image::ImageError),
+    /// The KTX2 container is malformed
+    InvalidKtx2(&'static str),
+    /// The KTX2 container uses a pixel format or supercompression that isn't supported
+    UnsupportedKtx2(String),
+    /// The texture could not be created
+    Creation(TextureCreationError),
+    /// The OpenGL context doesn't support the compressed format
+    UnsupportedCompression(PixelFormat),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Decode(err) => write!(f, "{err}"),
+            Self::InvalidKtx2(reason) => write!(f, "invalid KTX2 file: {reason}"),
+            Self::UnsupportedKtx2(reason) => write!(f, "unsupported KTX2 file: {reason}"),
+            Self::Creation(err) => write!(f, "could not create texture: {err}"),
+            Self::UnsupportedCompression(format) => write!(f, "compressed format {format:?} is not supported"),
+        }
+    }
+}
+
+impl error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Decode(err) => Some(err),
+            Self::Creation(err) => Some(err),
+            Self::InvalidKtx2(_) | Self::UnsupportedKtx2(_) | Self::UnsupportedCompression(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for TextureError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<image::ImageError> for TextureError {
+    fn from(err: image::ImageError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl From<TextureCreationError> for TextureError {
+    fn from(err: TextureCreationError) -> Self {
+        Self::Creation(err)
+    }
+}
+
+/// How the color channels of a texture are to be interpreted.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// colors meant to be seen, such as base color or emissive maps
+    #[default]
+    Srgb,
+    /// data such as normal, roughness or occlusion maps
+    Linear,
+}
+
+/// Layout of the pixels of a [`TextureData`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8 bits per channel
+    Rgba8,
+    /// BC1 without alpha, 8 bytes per 4×4 block
+    Bc1,
+    /// BC1 with 1 bit alpha
+    Bc1Alpha,
+    /// BC2 (DXT3), 16 bytes per block
+    Bc2,
+    /// BC3 (DXT5), 16 bytes per block
+    Bc3,
+    /// BC7 (BPTC), 16 bytes per block
+    Bc7,
+}
+
+impl PixelFormat {
+
+    /// Number of bytes taken by an image of the given size.
+    fn size(self, width: u32, height: u32) -> usize {
+        let blocks = || width.div_ceil(4) as usize * height.div_ceil(4) as usize;
+        match self {
+            Self::Rgba8 => width as usize * height as usize * 4,
+            Self::Bc1 | Self::Bc1Alpha => blocks() * 8,
+            Self::Bc2 | Self::Bc3 | Self::Bc7 => blocks() * 16,
+        }
+    }
+
+    fn compressed(self) -> Option<CompressedFormat> {
+        match self {
+            Self::Rgba8 => None,
+            Self::Bc1 => Some(CompressedFormat::S3tcDxt1NoAlpha),
+            Self::Bc1Alpha => Some(CompressedFormat::S3tcDxt1Alpha),
+            Self::Bc2 => Some(CompressedFormat::S3tcDxt3Alpha),
+            Self::Bc3 => Some(CompressedFormat::S3tcDxt5Alpha),
+            Self::Bc7 => Some(CompressedFormat::BptcUnorm4),
+        }
+    }
+
+    fn compressed_srgb(self) -> Option<CompressedSrgbFormat> {
+        match self {
+            Self::Rgba8 => None,
+            Self::Bc1 => Some(CompressedSrgbFormat::S3tcDxt1NoAlpha),
+            Self::Bc1Alpha => Some(CompressedSrgbFormat::S3tcDxt1Alpha),
+            Self::Bc2 => Some(CompressedSrgbFormat::S3tcDxt3Alpha),
+            Self::Bc3 => Some(CompressedSrgbFormat::S3tcDxt5Alpha),
+            Self::Bc7 => Some(CompressedSrgbFormat::Bptc),
+        }
+    }
+
+}
+
+/// A decoded texture in main memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureData {
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    color_space: ColorSpace,
+    /// the full size image followed by the stored mipmaps, each with the bottom row first
+    levels: Vec<Vec<u8>>,
+}
+
+impl TextureData {
+
+    /// Reads a PNG, JPEG or KTX2 file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or decoded.
+    pub fn load(path: &Path, color_space: ColorSpace) -> Result<Self, TextureError> {
+        debug!("load texture {}", path.display());
+        Self::decode(&fs::read(path)?, color_space)
+    }
+
+    /// Decodes a PNG, JPEG or KTX2 file, telling them apart by their content.
+    ///
+    /// KTX2 files bring their own color space, so `color_space` only applies to the other formats. Only KTX2 files
+    /// without supercompression and with RGB(A) or BC1-3/BC7 pixels are supported; compressed ones have to be stored
+    /// with the origin at the bottom left (`toktx --lower_left_maps_to_s0t0`), since their blocks cannot be flipped.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `data` is neither a valid image nor a supported KTX2 file.
+    pub fn decode(data: &[u8], color_space: ColorSpace) -> Result<Self, TextureError> {
+        if data.starts_with(&KTX2_IDENTIFIER) {
+            return decode_ktx2(data);
+        }
+
+        let mut image = image::load_from_memory(data)?.into_rgba8();
+        // texture coordinates have their origin at the bottom left
+        image::imageops::flip_vertical_in_place(&mut image);
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            format: PixelFormat::Rgba8,
+            color_space,
+            levels: vec![image.into_raw()],
+        })
+    }
+
+    #[must_use]
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[must_use]
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    #[must_use]
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Number of stored levels including the full size image.
+    #[must_use]
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Creates a texture with all mipmap levels.
+    ///
+    /// Stored mipmaps are uploaded as they are. Otherwise they are generated for uncompressed textures, while
+    /// compressed ones end up without mipmaps.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the texture cannot be created, e.g. because the compressed format isn't supported.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn upload<F>(&self, facade: &F) -> Result<Texture, TextureError>
+    where
+        F: Facade + ?Sized
+    {
+        let max_level = self.levels.len() as u32 - 1;
+        let levels = || self.levels.iter().enumerate().skip(1).map(|(level, data)| {
+            let (width, height) = level_size(self.width, self.height, level as u32);
+            (level as u32, Rect { left: 0, bottom: 0, width, height }, data)
+        });
+        let raw = |data: &Vec<u8>, rect: Rect| RawImage2d {
+            data: Cow::Owned(data.clone()),
+            width: rect.width,
+            height: rect.height,
+            format: ClientFormat::U8U8U8U8,
+        };
+        let full = Rect { left: 0, bottom: 0, width: self.width, height: self.height };
+
+        let texture = match (self.format, self.color_space) {
+            (PixelFormat::Rgba8, color_space) => {
+                let mipmaps = if max_level == 0 { MipmapsOption::AutoGeneratedMipmaps } else { MipmapsOption::EmptyMipmapsMax(max_level) };
+                if color_space == ColorSpace::Srgb {
+                    let texture = SrgbTexture2d::with_mipmaps(facade, raw(&self.levels[0], full), mipmaps)?;
+                    for (level, rect, data) in levels() {
+                        if let Some(mipmap) = texture.mipmap(level) {
+                            mipmap.write(rect, raw(data, rect));
+                        }
+                    }
+                    Texture::Srgb(texture)
+                } else {
+                    let texture = Texture2d::with_mipmaps(facade, raw(&self.levels[0], full), mipmaps)?;
+                    for (level, rect, data) in levels() {
+                        if let Some(mipmap) = texture.mipmap(level) {
+                            mipmap.write(rect, raw(data, rect));
+                        }
+                    }
+                    Texture::Linear(texture)
+                }
+            },
+            (format, ColorSpace::Srgb) => {
+                let Some(compressed) = format.compressed_srgb() else { unreachable!("{:?} is not compressed", format) };
+                let mipmaps = if max_level == 0 { CompressedMipmapsOption::NoMipmap } else { CompressedMipmapsOption::EmptyMipmapsMax(max_level) };
+                let texture = CompressedSrgbTexture2d::with_compressed_data(facade, &self.levels[0], self.width, self.height, compressed, mipmaps)?;
+                for (level, rect, data) in levels() {
+                    texture.mipmap(level)
+                            .ok_or(TextureError::UnsupportedCompression(format))?
+                            .write_compressed_data(rect, data, rect.width, rect.height, compressed)
+                            .map_err(|()| TextureError::UnsupportedCompression(format))?;
+                }
+                Texture::CompressedSrgb(texture)
+            },
+            (format, ColorSpace::Linear) => {
+                let Some(compressed) = format.compressed() else { unreachable!("{:?} is not compressed", format) };
+                let mipmaps = if max_level == 0 { CompressedMipmapsOption::NoMipmap } else { CompressedMipmapsOption::EmptyMipmapsMax(max_level) };
+                let texture = CompressedTexture2d::with_compressed_data(facade, &self.levels[0], self.width, self.height, compressed, mipmaps)?;
+                for (level, rect, data) in levels() {
+                    texture.mipmap(level)
+                            .ok_or(TextureError::UnsupportedCompression(format))?
+                            .write_compressed_data(rect, data, rect.width, rect.height, compressed)
+                            .map_err(|()| TextureError::UnsupportedCompression(format))?;
+                }
+                Texture::Compressed(texture)
+            },
+        };
+        Ok(texture)
+    }
+
+}
+
+/// A texture on the GPU in any of the supported formats.
+pub enum Texture {
+    Srgb(SrgbTexture2d),
+    Linear(Texture2d),
+    CompressedSrgb(CompressedSrgbTexture2d),
+    Compressed(CompressedTexture2d),
+}
+
+impl Texture {
+
+    /// Creates a 1×1 white texture, which leaves colors unchanged when multiplied with; meant for materials without
+    /// a texture.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the texture cannot be created.
+    pub fn white<F>(facade: &F) -> Result<Self, TextureError>
+    where
+        F: Facade + ?Sized
+    {
+        let data = TextureData {
+            width: 1,
+            height: 1,
+            format: PixelFormat::Rgba8,
+            color_space: ColorSpace::Srgb,
+            levels: vec![vec![255; 4]],
+        };
+        data.upload(facade)
+    }
+
+    #[must_use]
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Srgb(texture) => texture.dimensions(),
+            Self::Linear(texture) => texture.dimensions(),
+            Self::CompressedSrgb(texture) => texture.dimensions(),
+            Self::Compressed(texture) => texture.dimensions(),
+        }
+    }
+
+    /// Number of mipmap levels including the full size image.
+    #[must_use]
+    pub fn mipmap_levels(&self) -> u32 {
+        match self {
+            Self::Srgb(texture) => texture.get_mipmap_levels(),
+            Self::Linear(texture) => texture.get_mipmap_levels(),
+            Self::CompressedSrgb(texture) => texture.get_mipmap_levels(),
+            Self::Compressed(texture) => texture.get_mipmap_levels(),
+        }
+    }
+
+    /// Returns a repeating, trilinear filtered sampler for this texture to be passed as uniform.
+    ///
+    /// `max_anisotropy` should have been limited by [`anisotropy`] before.
+    #[must_use]
+    pub fn sampled(&self, max_anisotropy: u16) -> Sampled<'_> {
+        let minify_filter = if self.mipmap_levels() > 1 { MinifySamplerFilter::LinearMipmapLinear } else { MinifySamplerFilter::Linear };
+        Sampled {
+            texture: self,
+            behavior: SamplerBehavior {
+                wrap_function: (SamplerWrapFunction::Repeat, SamplerWrapFunction::Repeat, SamplerWrapFunction::Repeat),
+                minify_filter,
+                magnify_filter: MagnifySamplerFilter::Linear,
+                max_anisotropy: max_anisotropy.max(1),
+                ..SamplerBehavior::default()
+            },
+        }
+    }
+
+}
+
+/// A [`Texture`] along with the way to sample it.
+#[derive(Copy, Clone)]
+pub struct Sampled<'a> {
+    texture: &'a Texture,
+    behavior: SamplerBehavior,
+}
+
+impl AsUniformValue for Sampled<'_> {
+    fn as_uniform_value(&self) -> UniformValue<'_> {
+        match self.texture {
+            Texture::Srgb(texture) => UniformValue::SrgbTexture2d(texture, Some(self.behavior)),
+            Texture::Linear(texture) => UniformValue::Texture2d(texture, Some(self.behavior)),
+            Texture::CompressedSrgb(texture) => UniformValue::CompressedSrgbTexture2d(texture, Some(self.behavior)),
+            Texture::Compressed(texture) => UniformValue::CompressedTexture2d(texture, Some(self.behavior)),
+        }
+    }
+}
+
+/// Limits the `requested` degree of anisotropic filtering to what the OpenGL implementation supports; 1 disables it.
+#[must_use]
+pub fn anisotropy(context: &Context, requested: u16) -> u16 {
+    let supported = context.get_max_anisotropy_support().unwrap_or(1);
+    requested.clamp(1, supported.max(1))
+}
+
+/// Size of the given mipmap level, which is never smaller than 1×1.
+fn level_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+
+/// Parses a KTX2 container of a single 2D image, which is stored with the top row first unless compressed.
+fn decode_ktx2(data: &[u8]) -> Result<TextureData, TextureError> {
+    let read_u32 = |offset: usize| data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .ok_or(TextureError::InvalidKtx2("truncated header"));
+    let read_u64 = |offset: usize| -> Result<u64, TextureError> { Ok(u64::from(read_u32(offset)?) | u64::from(read_u32(offset + 4)?) << 32) };
+
+    let vk_format = read_u32(12)?;
+    let (width, height, depth) = (read_u32(20)?, read_u32(24)?, read_u32(28)?);
+    let (layers, faces, level_count, supercompression) = (read_u32(32)?, read_u32(36)?, read_u32(40)?, read_u32(44)?);
+
+    if supercompression != 0 {
+        return Err(TextureError::UnsupportedKtx2(format!("supercompression scheme {supercompression}")));
+    }
+    if depth > 0 || layers > 0 || faces != 1 || width == 0 || height == 0 {
+        return Err(TextureError::UnsupportedKtx2("only simple 2D textures are supported".to_string()));
+    }
+    let (format, color_space, channels) = match vk_format {
+        23 => (PixelFormat::Rgba8, ColorSpace::Linear, 3),
+        29 => (PixelFormat::Rgba8, ColorSpace::Srgb, 3),
+        37 => (PixelFormat::Rgba8, ColorSpace::Linear, 4),
+        43 => (PixelFormat::Rgba8, ColorSpace::Srgb, 4),
+        131 => (PixelFormat::Bc1, ColorSpace::Linear, 0),
+        132 => (PixelFormat::Bc1, ColorSpace::Srgb, 0),
+        133 => (PixelFormat::Bc1Alpha, ColorSpace::Linear, 0),
+        134 => (PixelFormat::Bc1Alpha, ColorSpace::Srgb, 0),
+        135 => (PixelFormat::Bc2, ColorSpace::Linear, 0),
+        136 => (PixelFormat::Bc2, ColorSpace::Srgb, 0),
+        137 => (PixelFormat::Bc3, ColorSpace::Linear, 0),
+        138 => (PixelFormat::Bc3, ColorSpace::Srgb, 0),
+        145 => (PixelFormat::Bc7, ColorSpace::Linear, 0),
+        146 => (PixelFormat::Bc7, ColorSpace::Srgb, 0),
+        _ => return Err(TextureError::UnsupportedKtx2(format!("Vulkan format {vk_format}"))),
+    };
+
+    // a level count of 0 asks for the mipmaps to be generated
+    let mut levels = Vec::new();
+    for level in 0..level_count.max(1) {
+        let index = 80 + level as usize * 24;
+        let (offset, length) = (read_u64(index)?, read_u64(index + 8)?);
+        let (level_width, level_height) = level_size(width, height, level);
+        let expected = if channels == 3 { level_width as usize * level_height as usize * 3 } else { format.size(level_width, level_height) };
+
+        let level_data = usize::try_from(offset).ok()
+                .zip(usize::try_from(length).ok())
+                .filter(|&(_, length)| length >= expected)
+                .and_then(|(offset, _)| data.get(offset..offset.checked_add(expected)?))
+                .ok_or(TextureError::InvalidKtx2("level data out of bounds"))?;
+
+        let level_data = match channels {
+            0 => level_data.to_vec(),
+            // rows are tightly packed for 1 byte channels, so the top row first order can be reversed row by row
+            3 => flip_rows(&level_data.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect::<Vec<_>>(), level_width),
+            _ => flip_rows(level_data, level_width),
+        };
+        levels.push(level_data);
+    }
+
+    Ok(TextureData { width, height, format, color_space, levels })
+}
+
+/// Reverses the order of the rows of an RGBA image.
+fn flip_rows(data: &[u8], width: u32) -> Vec<u8> {
+    data.chunks_exact(width as usize * 4).rev().flatten().copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba, RgbaImage};
+
+    fn ktx2(vk_format: u32, width: u32, height: u32, levels: &[&[u8]]) -> Vec<u8> {
+        let mut data = KTX2_IDENTIFIER.to_vec();
+        #[allow(clippy::cast_possible_truncation)]
+        let level_count = levels.len() as u32;
+        for value in [vk_format, 1, width, height, 0, 0, 1, level_count, 0, 0, 0, 0, 0, 0, 0, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut offset = data.len() + levels.len() * 24;
+        for level in levels {
+            for value in [offset, level.len(), level.len()] {
+                data.extend_from_slice(&(value as u64).to_le_bytes());
+            }
+            offset += level.len();
+        }
+        for level in levels {
+            data.extend_from_slice(level);
+        }
+        data
+    }
+
+    #[test]
+    fn images_are_flipped_to_bottom_row_first() {
+        let mut image = RgbaImage::from_pixel(1, 2, Rgba([0, 0, 0, 255]));
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image).write_to(&mut png, ImageFormat::Png).unwrap();
+
+        let texture = TextureData::decode(&png, ColorSpace::Linear).unwrap();
+        assert_eq!(texture.dimensions(), (1, 2));
+        assert_eq!(texture.color_space(), ColorSpace::Linear);
+        assert_eq!(texture.levels[0], vec![0, 0, 0, 255, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn ktx2_levels_are_read() {
+        let base: Vec<u8> = (0..2 * 2 * 3).collect();
+        let data = ktx2(29, 2, 2, &[&base, &[100, 101, 102]]);
+        let texture = TextureData::decode(&data, ColorSpace::Linear).unwrap();
+
+        assert_eq!((texture.format(), texture.color_space(), texture.level_count()), (PixelFormat::Rgba8, ColorSpace::Srgb, 2));
+        assert_eq!(texture.levels[0], vec![6, 7, 8, 255, 9, 10, 11, 255, 0, 1, 2, 255, 3, 4, 5, 255]);
+        assert_eq!(texture.levels[1], vec![100, 101, 102, 255]);
+
+        let block = [0; 16];
+        let texture = TextureData::decode(&ktx2(146, 5, 3, &[&[block; 2].concat()]), ColorSpace::Linear).unwrap();
+        assert_eq!(texture.format(), PixelFormat::Bc7);
+    }
+
+    #[test]
+    fn broken_ktx2_files_are_rejected() {
+        let data = ktx2(37, 2, 2, &[&[0; 8]]);
+        assert!(matches!(TextureData::decode(&data, ColorSpace::Srgb), Err(TextureError::InvalidKtx2(_))));
+        let data = ktx2(1000, 1, 1, &[&[0; 4]]);
+        assert!(matches!(TextureData::decode(&data, ColorSpace::Srgb), Err(TextureError::UnsupportedKtx2(_))));
+        assert!(matches!(TextureData::decode(&data[..30], ColorSpace::Srgb), Err(TextureError::InvalidKtx2(_))));
+        let data = ktx2(37, 0, 1, &[&[]]);
+        assert!(matches!(TextureData::decode(&data, ColorSpace::Srgb), Err(TextureError::UnsupportedKtx2(_))));
+
+        // a level offset close to the end of the address space must not overflow
+        let mut data = ktx2(37, 1, 1, &[&[0; 4]]);
+        data[80..88].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        assert!(matches!(TextureData::decode(&data, ColorSpace::Srgb), Err(TextureError::InvalidKtx2(_))));
+    }
+}
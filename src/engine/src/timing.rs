@@ -0,0 +1,123 @@
+use std::time::{Duration, Instant};
+
+/// Number of simulation ticks per second unless configured otherwise
+pub const DEFAULT_TICK_RATE: u32 = 60;
+
+/// Longest frame time that will be simulated; anything beyond that is dropped to avoid a spiral of death
+pub const DEFAULT_MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+/// The result of advancing the clock by one rendered frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Steps {
+    /// Number of simulation ticks to run before rendering
+    pub ticks: u32,
+    /// Position of the rendered frame between the previous (0.0) and the current (1.0) simulation state
+    pub alpha: f32,
+}
+
+/// Converts measured frame times into a whole number of fixed-size simulation ticks.
+///
+/// The time left over after running all complete ticks is carried over into the next frame and reported as
+/// interpolation factor, so the simulation is independent from the render rate.
+pub struct FixedStep {
+    tick_rate: u32,
+    tick: Duration,
+    max_frame_time: Duration,
+    accumulator: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl Default for FixedStep {
+    fn default() -> Self {
+        Self::new(DEFAULT_TICK_RATE, DEFAULT_MAX_FRAME_TIME)
+    }
+}
+
+impl FixedStep {
+
+    /// Creates a clock running `tick_rate` simulation ticks per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick_rate` is zero.
+    #[must_use]
+    pub fn new(tick_rate: u32, max_frame_time: Duration) -> Self {
+        assert!(tick_rate > 0, "tick rate must not be zero");
+        Self {
+            tick_rate,
+            tick: Duration::from_secs(1) / tick_rate,
+            max_frame_time,
+            accumulator: Duration::default(),
+            last_frame: None,
+        }
+    }
+
+    /// Number of simulation ticks per second.
+    #[must_use]
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    /// Duration of a single simulation tick.
+    #[must_use]
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    /// Measures the time since the previous call and returns how many ticks have to be simulated.
+    pub fn advance(&mut self, now: Instant) -> Steps {
+        // the very first frame doesn't simulate anything but shows the initial state
+        let frame_time = self.last_frame.map_or_else(Duration::default, |last_frame| now.saturating_duration_since(last_frame));
+        self.last_frame = Some(now);
+        self.advance_by(frame_time)
+    }
+
+    /// Adds `frame_time` to the clock and returns how many ticks have to be simulated.
+    pub fn advance_by(&mut self, frame_time: Duration) -> Steps {
+        self.accumulator += frame_time.min(self.max_frame_time);
+
+        let mut ticks = 0;
+        while self.accumulator >= self.tick {
+            self.accumulator -= self.tick;
+            ticks += 1;
+        }
+
+        Steps {
+            ticks,
+            alpha: self.accumulator.as_secs_f32() / self.tick.as_secs_f32(),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_over_partial_ticks() {
+        let mut step = FixedStep::new(100, DEFAULT_MAX_FRAME_TIME);
+
+        let steps = step.advance_by(Duration::from_millis(25));
+        assert_eq!(steps.ticks, 2);
+        assert!((steps.alpha - 0.5).abs() < 1e-4);
+
+        let steps = step.advance_by(Duration::from_millis(5));
+        assert_eq!(steps.ticks, 1);
+        assert!(steps.alpha.abs() < 1e-4);
+    }
+
+    #[test]
+    fn clamps_long_frames() {
+        let mut step = FixedStep::new(100, Duration::from_millis(50));
+
+        let steps = step.advance_by(Duration::from_secs(3));
+        assert_eq!(steps.ticks, 5);
+    }
+
+    #[test]
+    fn first_frame_does_not_tick() {
+        let mut step = FixedStep::default();
+        assert_eq!(step.advance(Instant::now()).ticks, 0);
+    }
+}
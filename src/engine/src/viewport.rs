@@ -0,0 +1,109 @@
+//! Splitting the window among several players sharing the screen.
+
+use serde::{Deserialize, Serialize};
+
+/// Number of players the screen can be split among at most.
+pub const MAX_PLAYERS: usize = 4;
+
+/// How the window is divided among the players.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitLayout {
+    /// full-width viewports stacked from top to bottom
+    Horizontal,
+    /// full-height viewports side by side from left to right
+    Vertical,
+    /// a 2×2 grid filled row by row from the top left; cells without a player stay empty
+    Quad,
+}
+
+/// A rectangle of the window in physical pixels, counted from the bottom left corner as OpenGL does.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub left: u32,
+    pub bottom: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+
+    /// The viewport covering a whole surface of the given size.
+    #[must_use]
+    pub fn full((width, height): (u32, u32)) -> Self {
+        Self { left: 0, bottom: 0, width, height }
+    }
+
+    /// Width divided by height, or 1.0 for an empty viewport.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.width == 0 || self.height == 0 {
+            1.0
+        } else {
+            self.width as f32 / self.height as f32
+        }
+    }
+
+    /// The viewport as glium expects it for draw parameters and clearing.
+    #[must_use]
+    pub fn rect(&self) -> glium::Rect {
+        glium::Rect { left: self.left, bottom: self.bottom, width: self.width, height: self.height }
+    }
+
+}
+
+/// Divides a surface of the given size into viewports for `players` players, the first one at the top or left.
+///
+/// `players` is limited to [`MAX_PLAYERS`]; a single player gets the whole surface regardless of the layout.
+#[must_use]
+pub fn split(layout: SplitLayout, players: usize, size: (u32, u32)) -> Vec<Viewport> {
+    let players = players.clamp(1, MAX_PLAYERS);
+    if players == 1 {
+        return vec![Viewport::full(size)];
+    }
+    let (columns, rows) = match layout {
+        SplitLayout::Horizontal => (1, players),
+        SplitLayout::Vertical => (players, 1),
+        SplitLayout::Quad => (2, 2),
+    };
+    (0..players).map(|index| cell(size, (columns, rows), (index % columns, index / columns))).collect()
+}
+
+/// Returns the cell of a grid of `columns` × `rows` counting rows from the top; the cells differ by a pixel at most.
+#[allow(clippy::cast_possible_truncation)]
+fn cell((width, height): (u32, u32), (columns, rows): (usize, usize), (column, row): (usize, usize)) -> Viewport {
+    // never exceeds `size`, so it fits into u32 again
+    let edge = |size: u32, index: usize, count: usize| (u64::from(size) * index as u64 / count as u64) as u32;
+    let (left, right) = (edge(width, column, columns), edge(width, column + 1, columns));
+    let (top, bottom) = (height - edge(height, row, rows), height - edge(height, row + 1, rows));
+    Viewport { left, bottom, width: right - left, height: top - bottom }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewports_cover_the_window() {
+        let viewports = split(SplitLayout::Horizontal, 3, (800, 601));
+        assert_eq!(viewports, [
+            Viewport { left: 0, bottom: 401, width: 800, height: 200 },
+            Viewport { left: 0, bottom: 201, width: 800, height: 200 },
+            Viewport { left: 0, bottom: 0, width: 800, height: 201 },
+        ]);
+
+        let viewports = split(SplitLayout::Vertical, 2, (800, 600));
+        assert_eq!(viewports[1], Viewport { left: 400, bottom: 0, width: 400, height: 600 });
+        assert!((viewports[0].aspect_ratio() - 400.0 / 600.0).abs() < 1e-6);
+
+        let viewports = split(SplitLayout::Quad, 3, (800, 600));
+        assert_eq!(viewports, [
+            Viewport { left: 0, bottom: 300, width: 400, height: 300 },
+            Viewport { left: 400, bottom: 300, width: 400, height: 300 },
+            Viewport { left: 0, bottom: 0, width: 400, height: 300 },
+        ]);
+
+        assert_eq!(split(SplitLayout::Quad, 1, (800, 600)), [Viewport::full((800, 600))]);
+    }
+}
@@ -1,33 +1,71 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::non_ascii_literal)]
 
-use crate::input::Control;
-use cgmath::Vector3;
-use std::io::Cursor;
+use cgmath::{Deg, InnerSpace, Quaternion, Rotation3, Vector3};
+use std::{env, fmt};
+use std::path::{Path, PathBuf};
 use std::process;
 use std::time::{Duration, Instant};
 
-use glium::{Surface, vertex::VertexBufferAny, glutin::dpi::{PhysicalSize, Size}, program, uniform};
-use glium::index::{ NoIndices, PrimitiveType };
-use glium::glutin::event::{ Event, WindowEvent, StartCause };
-use glium::glutin::event_loop::{ EventLoop, ControlFlow };
-use input::{bindings::Bindings, keyboard};
-use log::{debug, error};
-use model::load_stl;
+use glium::{Display, Surface};
+use glium::backend::Facade;
+use glium::glutin::event::{DeviceEvent, ElementState, VirtualKeyCode, WindowEvent};
+use log::{debug, error, info, warn};
+use reactor_ls_engine::{Action, App, Engine, GliumMatrix};
+use reactor_ls_engine::assets::{Asset, Assets, Handle, Vfs};
+use reactor_ls_engine::camera::{CameraEffects, CameraId, CameraManager, CameraRig, CameraState, ChaseCamera};
+use reactor_ls_engine::capture::{self, Offscreen};
+use reactor_ls_engine::display;
+use reactor_ls_engine::input::{Axes, Control, InputSource, gamepad, keyboard, mouse};
+use reactor_ls_engine::input::bindings::{self, Bindings};
+use reactor_ls_engine::input::replay::{Player, Recording, Tick};
+use reactor_ls_engine::logging::{self, LogSettings};
+use reactor_ls_engine::model::{Mesh, MeshBuffers, UploadError, Weighting};
+use reactor_ls_engine::scene::{Drawable, LIGHT_DEFINES, Light, Material, MeshId, NodeId, Renderer, SceneGraph, Transform};
+use reactor_ls_engine::settings::{self, Settings};
+use reactor_ls_engine::shader::ShaderProgram;
+use reactor_ls_engine::timing;
+use reactor_ls_engine::viewport::{self, Viewport};
 
-mod display;
-mod model;
-mod camera;
-mod input;
+use options::Options;
 
-pub type GliumMatrix = [[f32; 4]; 4];
+mod options;
+
+/// Name of the application as used for configuration directories
+const APPLICATION_NAME: &str = "reactor-ls";
+
+/// Edges between triangles whose normals differ by more than this angle are shaded hard
+const CREASE_ANGLE: Deg<f32> = Deg(30.0);
+
+/// Logical paths of the assets making up the scene
+const MESH_PATH: &str = "axis.stl";
+const VERTEX_SHADER_PATH: &str = "textured.vertex.glsl";
+const FRAGMENT_SHADER_PATH: &str = "textured.fragment.glsl";
+
+/// Time between two checks of the watched files for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Colors of the cockpits telling the ships of the players apart
+const PLAYER_COLORS: [[f32; 4]; 4] = [
+    [0.3, 0.6, 1.0, 1.0],
+    [1.0, 0.3, 0.3, 1.0],
+    [0.3, 0.9, 0.4, 1.0],
+    [1.0, 0.85, 0.2, 1.0],
+];
 
 #[repr(i32)]
 pub enum ExitCode {
     //Ok = 0,
-    LoadMesh = 1,
+    LoadAssets = 1,
     CreateDisplay = 2,
     CreateShaderProgram = 3,
+    LoadSettings = 4,
+    InitLogging = 5,
+    LoadBindings = 6,
+    InvalidArguments = 7,
+    LoadRecording = 8,
+    SaveScreenshot = 9,
+    LoadTexture = 10,
 }
 
 impl From<ExitCode> for i32 {
@@ -36,153 +74,605 @@ impl From<ExitCode> for i32 {
     }
 }
 
-pub enum Action {
-    Stop,
-    Continue,
+/// Everything that is drawn along with the assets it has been created from.
+struct Scene {
+    graph: SceneGraph,
+    renderer: Renderer,
+    program: ShaderProgram,
+    mesh_asset: Handle<Mesh>,
+    /// version of the mesh the buffers have been created from
+    mesh_version: u32,
+    mesh: MeshId,
+    /// the ship of each player
+    ships: Vec<NodeId>,
 }
 
-fn main() {
-    pretty_env_logger::init();
+/// What a player sees: the camera matrices and the part of the window to draw into.
+struct PlayerView {
+    viewport: Viewport,
+    persp_matrix: GliumMatrix,
+    view_matrix: GliumMatrix,
+}
 
-    debug!("load mesh");
-    let vertex_data = load_stl(&mut Cursor::new(include_bytes!("../../../res/axis.stl")))
-            .unwrap_or_else(|err| {
-                error!("Could not parse stl: {}", err);
-                process::exit(ExitCode::LoadMesh as i32)
-            });
+impl Scene {
 
+    fn new<F: Facade>(facade: &F, assets: &mut Assets, settings: &Settings) -> Self {
+        let mesh_asset = load_asset::<Mesh>(assets, MESH_PATH);
+        let buffers = upload_mesh(facade, &mesh_asset).unwrap_or_else(|err| {
+            error!("Could not upload mesh: {err}");
+            process::exit(ExitCode::LoadAssets as i32)
+        });
 
-    debug!("create display");
-    let event_loop = EventLoop::new();
-    let window_width = 1024;
-    let window_height = 768;
-    #[allow(clippy::clippy::cast_precision_loss)]
-    let aspect_ratio = window_width as f32 / window_height as f32;
+        debug!("create shader program");
+        let program = ShaderProgram::load(facade, assets, VERTEX_SHADER_PATH, FRAGMENT_SHADER_PATH, &LIGHT_DEFINES).unwrap_or_else(|err| {
+            error!("Could not create shader program: {err}");
+            process::exit(ExitCode::CreateShaderProgram as i32)
+        });
+        debug!("compiled shaders for {:?}", program.target());
 
-    let display = display::create(&event_loop, Size::Physical(PhysicalSize::new(window_width, window_height)))
-            .unwrap_or_else(|err| {
-                error!("Could not create display: {}", err);
-                process::exit(ExitCode::CreateDisplay as i32)
+        // the mesh doesn't come with a texture yet
+        let renderer = Renderer::new(facade, settings.graphics.anisotropy).unwrap_or_else(|err| {
+            error!("Could not create texture: {err}");
+            process::exit(ExitCode::LoadTexture as i32)
+        });
+
+        // a stand-in for a ship until there are proper models: the axes with smaller copies attached
+        let mut graph = SceneGraph::new();
+        let mesh = graph.add_mesh(buffers);
+        let add_part = |graph: &mut SceneGraph, parent, name: &str, material: Material, transform: Transform| {
+            let material = graph.add_material(material);
+            graph.add_node(parent, name, transform, Some(Drawable { mesh, material }))
+        };
+        let colored = |base_color| Material { base_color, ..Material::default() };
+        let mut ships = Vec::new();
+        for (index, &color) in PLAYER_COLORS.iter().enumerate().take(settings.players.count) {
+            let start = Transform::from_translation(start_position(index, settings.players.count));
+            let ship = add_part(&mut graph, None, "ship", colored([1.0; 4]), start);
+            let engine = add_part(&mut graph, Some(ship), "engine", colored([0.6, 0.6, 0.7, 1.0]), Transform {
+                translation: Vector3::new(0.0, 0.0, 0.5),
+                scale: Vector3::new(0.4, 0.4, 0.4),
+                ..Transform::default()
             });
+            let flame = Material { emissive: [1.0, 0.4, 0.1], specular: [0.0; 3], ..colored([1.0, 0.5, 0.1, 1.0]) };
+            let flame = add_part(&mut graph, Some(engine), "flame", flame, Transform {
+                translation: Vector3::new(0.0, 0.0, 1.0),
+                rotation: Quaternion::from_angle_y(Deg(180.0)),
+                scale: Vector3::new(0.5, 0.5, 1.5),
+            });
+            add_part(&mut graph, Some(ship), "cockpit", colored(color), Transform {
+                translation: Vector3::new(0.0, 0.3, -0.3),
+                scale: Vector3::new(0.25, 0.25, 0.25),
+                ..Transform::default()
+            });
+            graph.add_light(Some(flame), "engine glow", Transform::default(), Light::point(3.0, [1.0, 0.5, 0.2], 2.0));
+            ships.push(ship);
+        }
 
-    display::dump_details(&display);
+        graph.add_light(None, "sun", Transform::default(), Light::directional(Vector3::new(0.2, -0.8, -0.1), [1.0, 0.95, 0.9], 0.9));
+        graph.add_light(None, "sky", Transform::default(), Light::ambient([0.6, 0.7, 1.0], 0.1));
+        for (name, x) in &[("left lamp", -2.0), ("right lamp", 2.0)] {
+            let transform = Transform::from_translation(Vector3::new(*x, 0.5, -1.0));
+            graph.add_light(None, name, transform, Light::point(4.0, [0.9, 0.9, 0.6], 1.5));
+        }
 
-    debug!("create vertex buffer from mesh");
-    let vertex_buffer: VertexBufferAny = glium::vertex::VertexBuffer::new(&display, &vertex_data).unwrap().into();
+        let mesh_version = mesh_asset.version();
+        Self { graph, renderer, program, mesh_asset, mesh_version, mesh, ships }
+    }
 
-    debug!("create shader program");
-    let program = program!(&display,
-        140 => {
-            vertex: include_str!("../../../res/demo.vertex.140.glsl"),
-            fragment: include_str!("../../../res/demo.fragment.140.glsl"),
-        },
-    ).unwrap_or_else(|err| {
-        error!("Could not create shader program: {}", err);
-        process::exit(ExitCode::CreateShaderProgram as i32)
-    });
+    /// Moves a ship to the position and orientation of `pose`.
+    fn place_ship(&mut self, ship: NodeId, pose: &CameraState) {
+        let transform = &mut self.graph.node_mut(ship).transform;
+        transform.translation = pose.position();
+        transform.rotation = pose.rotation();
+    }
 
-    let mut control = Control::new(1.0);
-    let mut keyboard = keyboard::Control::default();
-    let cam_position = Vector3::new(1.0, 1.0, 1.0);
-    let cam_look_at = Vector3::new(0.0, 0.0, 0.0);
-    let mut camera = camera::CameraState::new(cam_position, cam_look_at, aspect_ratio);
+    /// Recreates the GPU resources of assets that have been reloaded; keeps the previous ones if that fails.
+    fn reload<F: Facade>(&mut self, facade: &F, assets: &mut Assets) {
+        if self.mesh_asset.version() != self.mesh_version {
+            self.mesh_version = self.mesh_asset.version();
+            match upload_mesh(facade, &self.mesh_asset) {
+                Ok(buffers) => self.graph.replace_mesh(self.mesh, buffers),
+                Err(err) => error!("Could not upload mesh, keeping the previous one: {err}"),
+            }
+        }
+        if let Err(err) = self.program.reload(facade, assets) {
+            error!("Could not compile shaders, keeping the previous program: {err}");
+        }
+    }
 
-    debug!("start main loop …");
-    start_loop(event_loop, move |events| {
-        //camera.update_position();
-        // TODO insert proper duration
-        control.update(1.0 / 30.0);
-        camera.update_position(&control);
-
-        // building the uniforms
-        let persp_matrix: GliumMatrix = camera.get_perspective().into();
-        let view_matrix: GliumMatrix = camera.get_view().into();
-        let uniforms = uniform! { persp_matrix: persp_matrix, view_matrix: view_matrix, };
-
-        // draw parameters
-        let params = glium::DrawParameters {
-            depth: glium::Depth {
-                test: glium::DepthTest::IfLess,
-                write: true,
-                .. glium::Depth::default()
+    /// Clears the whole target, so parts not covered by any view stay black, and draws each view.
+    fn draw<S: Surface>(&self, target: &mut S, views: &[PlayerView]) {
+        target.clear_color_and_depth((0.0, 0.0, 0.0, 0.0), 1.0);
+        for view in views {
+            self.renderer.draw(target, &self.graph, self.program.program(), view.persp_matrix, view.view_matrix, Some(view.viewport)).unwrap();
+        }
+    }
+
+}
+
+/// A player along with the ship they steer and the cameras following it.
+struct Pilot {
+    /// the devices steering the ship; `None` for a single player, who uses all devices at once
+    input: Option<InputSource>,
+    control: Control,
+    /// all cameras following the ship
+    cameras: CameraManager,
+    /// the camera flown by the input instead of the ship while it is active
+    free_camera: CameraId,
+    chase_camera: CameraId,
+    /// position and orientation of the ship, which flies like the free camera for now
+    ship: CameraState,
+    /// state of the ship before the most recent simulation tick; used for interpolation
+    previous_ship: CameraState,
+    /// the node of the ship in the scene
+    ship_node: NodeId,
+    /// applied to whichever camera is active
+    effects: CameraEffects,
+    /// speed of the steered camera or ship during the previous tick, unless the cameras have just been switched
+    speed: Option<f32>,
+}
+
+impl Pilot {
+
+    /// Creates the player steering the ship placed at `ship_node` in the scene.
+    fn new(settings: &Settings, input: Option<InputSource>, scene: &Scene, ship_node: NodeId) -> Self {
+        // ships head along the negative z axis as placed in the scene
+        let start = scene.graph.node(ship_node).transform.translation;
+        let ship = create_pose(settings, start, start + Vector3::new(0.0, 0.0, -1.0));
+        let camera = create_pose(settings, start + Vector3::new(1.0, 1.0, 1.0), start);
+
+        let mut cameras = CameraManager::new("free", CameraRig::Free(camera.clone()));
+        let free_camera = cameras.active();
+        let chase_camera = cameras.add("chase", CameraRig::Chase(ChaseCamera::new(settings.camera.chase.clone(), camera.clone())));
+        let mount = |x, y, z| CameraRig::Mounted { camera: camera.clone(), offset: Transform::from_translation(Vector3::new(x, y, z)) };
+        cameras.add("cockpit", mount(0.0, 0.5, -0.3));
+        cameras.add("bumper", mount(0.0, -0.1, -1.2));
+        cameras.add("track-side", CameraRig::Tracking(create_pose(settings, Vector3::new(6.0, 2.0, -8.0), start)));
+
+        Self {
+            input,
+            control: Control::with_responses(settings.input.gradient_speed, &settings.input.response),
+            cameras,
+            free_camera,
+            chase_camera,
+            previous_ship: ship.clone(),
+            ship,
+            ship_node,
+            effects: CameraEffects::new(settings.camera.effects.clone()),
+            speed: None,
+        }
+    }
+
+    /// Whether the input flies the free camera rather than the ship.
+    fn steers_free_camera(&self) -> bool {
+        self.cameras.active() == self.free_camera && !self.cameras.has_temporary()
+    }
+
+    /// The camera or ship the input steers.
+    fn steered(&self) -> &CameraState {
+        if self.steers_free_camera() {
+            self.cameras.rig(self.free_camera).camera()
+        } else {
+            &self.ship
+        }
+    }
+
+    /// Cycles `next_camera` cameras onwards and lets the chase camera look behind if requested.
+    fn switch_cameras(&mut self, next_camera: u32, look_behind: bool, blend_time: f32) {
+        if next_camera > 0 {
+            self.cameras.cycle(next_camera as usize, blend_time);
+            self.effects.reset();
+            self.speed = None;
+            info!("switched to {} camera", self.cameras.name(self.cameras.active()));
+        }
+        if let CameraRig::Chase(chase) = self.cameras.rig_mut(self.chase_camera) {
+            chase.set_look_behind(look_behind);
+        }
+    }
+
+    /// Moves the ship or free camera as requested by `axes` for `time` seconds, followed by the cameras.
+    fn update(&mut self, axes: Axes, scene: &mut Scene, time: f32) {
+        self.previous_ship = self.ship.clone();
+        let previous_position = self.steered().position();
+
+        self.control.set_axes(axes);
+        self.control.update(time);
+        if self.steers_free_camera() {
+            self.cameras.rig_mut(self.free_camera).camera_mut().update_position(&self.control, time);
+        } else {
+            self.ship.update_position(&self.control, time);
+        }
+        scene.place_ship(self.ship_node, &self.ship);
+        self.cameras.update(&scene.graph.world_matrix(self.ship_node), time);
+
+        let speed = (self.steered().position() - previous_position).magnitude() / time;
+        // boosting and braking shake the camera
+        if let Some(previous_speed) = self.speed.replace(speed) {
+            self.effects.add_trauma(self.effects.settings().boost_trauma * (speed - previous_speed).abs());
+        }
+        self.effects.update(speed, self.control.yaw.value, time);
+    }
+
+    /// What the player sees in `viewport`, `alpha` of the way from the previous simulation tick to the last one.
+    fn view(&self, viewport: Viewport, alpha: f32) -> PlayerView {
+        let mut camera = self.effects.apply(&self.cameras.view(alpha), 1.0);
+        camera.set_aspect_ratio(viewport.aspect_ratio());
+        PlayerView { viewport, persp_matrix: camera.get_perspective().into(), view_matrix: camera.get_view().into() }
+    }
+
+}
+
+struct Game {
+    display: Display,
+    settings: Settings,
+    /// where to write the settings to if they have been changed
+    settings_path: Option<PathBuf>,
+    settings_changed: bool,
+    keyboard: keyboard::Control,
+    gamepad: Option<gamepad::Control>,
+    mouse: mouse::Control,
+    /// input of the first player in this session to be saved at the given path
+    recording: Option<(PathBuf, Recording)>,
+    /// recorded input replacing the live input of the first player
+    replay: Option<Player>,
+    /// all players in the order of their viewports
+    pilots: Vec<Pilot>,
+    assets: Assets,
+    /// when to check the files of the assets for changes next, if they are watched at all
+    next_watch: Option<Instant>,
+    scene: Scene,
+    /// whether the next frame shall be saved as screenshot
+    screenshot_requested: bool,
+}
+
+impl Game {
+
+    /// Returns the input requested by the devices of `input` right now; `next_camera` and `look_behind` are the
+    /// camera commands of the keyboard.
+    fn live_tick(&self, input: Option<InputSource>, next_camera: u32, look_behind: bool) -> Tick {
+        let keyboard = Tick { axes: self.keyboard.axes() + self.mouse.axes(), next_camera, look_behind };
+        match (input, &self.gamepad) {
+            (None, Some(gamepad)) => Tick {
+                axes: keyboard.axes + gamepad.axes(),
+                next_camera: next_camera + gamepad.next_camera(),
+                look_behind: look_behind || gamepad.look_behind(),
             },
-            .. glium::DrawParameters::default()
-        };
+            (None | Some(InputSource::Keyboard), _) => keyboard,
+            (Some(input), Some(gamepad)) => input.gamepad_index().map_or_else(Tick::default, |index| Tick {
+                axes: gamepad.player_axes(index),
+                next_camera: gamepad.player_next_camera(index),
+                look_behind: gamepad.player_look_behind(index),
+            }),
+            (Some(_), None) => Tick::default(),
+        }
+    }
 
-        // drawing a frame
-        let mut target = display.draw();
-        target.clear_color_and_depth((0.0, 0.0, 0.0, 0.0), 1.0);
-        target.draw(&vertex_buffer, &NoIndices(PrimitiveType::TrianglesList), &program, &uniforms, &params).unwrap();
-        target.finish().unwrap();
-
-        let mut action = Action::Continue;
-
-        // polling and handling the events received by the window
-        for event in events {
-            #[allow(clippy::single_match)]
-            match event {
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::CloseRequested => action = Action::Stop,
-                    #[allow(clippy::clippy::cast_precision_loss)]
-                    WindowEvent::Resized(size) => camera.set_aspect_ratio(size.width as f32 / size.height as f32),
-                    WindowEvent::KeyboardInput { device_id: _device_id, input, is_synthetic: _is_synthetic } => {
-                        keyboard.process_keyboard_input(*input, &mut control);
-                    },
-                    _ => {}
-                },
-                _ => (),
-            }
+    /// Renders the views once more into a texture of `width` × `height` pixels and saves it into the screenshot
+    /// directory.
+    fn save_screenshot(&self, (width, height): (u32, u32), views: &[PlayerView]) {
+        let Some(dir) = capture::default_dir(APPLICATION_NAME) else {
+            warn!("Could not determine the screenshot directory");
+            return;
         };
+        let path = capture::screenshot_path(&dir);
 
-        action
-    });
+        let result = Offscreen::new(&self.display, width, height)
+                .and_then(|offscreen| offscreen.render(&self.display, |target| self.scene.draw(target, views)))
+                .and_then(|image| capture::save_png(&image, &path));
+        match result {
+            Ok(()) => info!("saved screenshot to {}", path.display()),
+            Err(err) => error!("Could not save screenshot to {}: {}", path.display(), err),
+        }
+    }
 
 }
 
-pub fn start_loop<F>(event_loop: EventLoop<()>, mut callback: F)->!
-where
-    F: 'static + FnMut(&Vec<Event<'_, ()>>) -> Action
-{
-    let mut events_buffer = Vec::new();
-    let mut next_frame_time = Instant::now();
-    event_loop.run(move |event, _, control_flow| {
-        let run_callback = match event.to_static() {
-            Some(Event::NewEvents(cause)) => {
-                match cause {
-                    StartCause::ResumeTimeReached { .. } | StartCause::Init => {
-                        true
-                    },
-                    _ => false
+impl App for Game {
+
+    fn handle_event(&mut self, event: &WindowEvent<'_>) -> Action {
+        match event {
+            WindowEvent::Resized(size) => {
+                // remember the window size for the next start
+                let window = &mut self.settings.window;
+                if size.width > 0 && size.height > 0 && (window.width, window.height) != (size.width, size.height) {
+                    window.width = size.width;
+                    window.height = size.height;
+                    self.settings_changed = true;
                 }
             },
-            Some(event) => {
-                events_buffer.push(event);
-                false
-            }
-            None => {
-                // Ignore this event.
-                false
+            WindowEvent::KeyboardInput { device_id: _device_id, input, is_synthetic: _is_synthetic } => {
+                // escape hands the cursor back to the user
+                if input.virtual_keycode == Some(VirtualKeyCode::Escape) && self.mouse.is_grabbed() {
+                    self.mouse.set_grabbed(self.display.gl_window().window(), false);
+                }
+                if input.virtual_keycode == Some(VirtualKeyCode::F12) && input.state == ElementState::Pressed {
+                    self.screenshot_requested = true;
+                }
+                self.keyboard.process_keyboard_input(*input);
             },
-        };
+            WindowEvent::MouseInput { state, button, .. } => {
+                // the first click only grabs the cursor
+                if *state == ElementState::Pressed && !self.mouse.is_grabbed() {
+                    self.mouse.set_grabbed(self.display.gl_window().window(), true);
+                } else {
+                    self.keyboard.process_mouse_input(*state, *button);
+                }
+            },
+            WindowEvent::Focused(false) => self.mouse.set_grabbed(self.display.gl_window().window(), false),
+            _ => {}
+        }
 
-        let action = if run_callback {
-            let action = callback(&events_buffer);
-            next_frame_time = Instant::now() + Duration::from_nanos(16_666_667);
-            // TODO: Add back the old accumulator loop in some way
+        Action::Continue
+    }
 
-            events_buffer.clear();
-            action
-        } else {
-            Action::Continue
-        };
+    fn handle_device_event(&mut self, event: &DeviceEvent) -> Action {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.mouse.process_motion(*delta);
+        }
 
-        match action {
-            Action::Continue => {
-                *control_flow = ControlFlow::WaitUntil(next_frame_time);
-            },
-            Action::Stop => *control_flow = ControlFlow::Exit
+        Action::Continue
+    }
+
+    fn update(&mut self, time: f32) {
+        let replayed = self.replay.as_mut().and_then(Player::next_tick);
+        if self.replay.is_some() && replayed.is_none() {
+            info!("replay finished at position {:?}", self.pilots[0].steered().position());
+            self.replay = None;
+        }
+
+        if let Some(gamepad) = &mut self.gamepad {
+            gamepad.update();
+        }
+        self.mouse.update();
+        let next_camera = self.keyboard.take_next_camera();
+        let look_behind = self.keyboard.look_behind();
+        let mut ticks: Vec<Tick> = self.pilots.iter().map(|pilot| self.live_tick(pilot.input, next_camera, look_behind)).collect();
+        if let Some(replayed) = replayed {
+            ticks[0] = replayed;
+        }
+
+        if let Some((_, recording)) = &mut self.recording {
+            recording.push(ticks[0]);
+        }
+        let blend_time = self.settings.camera.blend_time;
+        for (pilot, tick) in self.pilots.iter_mut().zip(ticks) {
+            pilot.switch_cameras(tick.next_camera, tick.look_behind, blend_time);
+            pilot.update(tick.axes, &mut self.scene, time);
+        }
+    }
+
+    fn draw<S: Surface>(&mut self, target: &mut S, alpha: f32) {
+        // checking the files outside of the simulation, which would otherwise stall once per tick
+        if let Some(next_watch) = &mut self.next_watch {
+            let now = Instant::now();
+            if now >= *next_watch {
+                *next_watch = now + WATCH_INTERVAL;
+                if self.assets.reload_changed() > 0 {
+                    self.scene.reload(&self.display, &mut self.assets);
+                }
+            }
+        }
+
+        for pilot in &self.pilots {
+            self.scene.place_ship(pilot.ship_node, &pilot.ship.interpolated(&pilot.previous_ship, alpha));
+        }
+        let viewports = viewport::split(self.settings.players.layout, self.pilots.len(), target.get_dimensions());
+        let views: Vec<PlayerView> = self.pilots.iter().zip(viewports).map(|(pilot, viewport)| pilot.view(viewport, alpha)).collect();
+        self.scene.draw(target, &views);
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            self.save_screenshot(target.get_dimensions(), &views);
+        }
+    }
+
+    fn shutdown(&mut self) {
+        if let Some((path, recording)) = &self.recording {
+            if let Err(err) = recording.save(path) {
+                error!("Could not save recording to {}: {}", path.display(), err);
+            }
         }
+
+        if let (true, Some(path)) = (self.settings_changed, &self.settings_path) {
+            if let Err(err) = self.settings.save(path) {
+                error!("Could not save settings to {}: {}", path.display(), err);
+            }
+        }
+    }
+
+}
+
+/// Provides a template for the user to edit unless there's already a file at `path`.
+fn save_template<E: fmt::Display>(path: &Path, name: &str, save: impl FnOnce(&Path) -> Result<(), E>) {
+    if !path.exists() {
+        if let Err(err) = save(path) {
+            warn!("Could not save {} to {}: {}", name, path.display(), err);
+        }
+    }
+}
+
+/// Uploads the mesh after recomputing its normals, since the ones stored in STL files are per facet and not always
+/// reliable.
+fn upload_mesh<F: Facade>(facade: &F, asset: &Handle<Mesh>) -> Result<MeshBuffers, UploadError> {
+    let mut mesh = asset.get().clone();
+    mesh.recompute_normals(CREASE_ANGLE, Weighting::Angle);
+    debug!("upload mesh of {} vertices and {} triangles", mesh.vertices().len(), mesh.triangle_count());
+    mesh.upload(facade)
+}
+
+fn load_asset<T: Asset>(assets: &mut Assets, path: &str) -> Handle<T> {
+    assets.load(path).unwrap_or_else(|err| {
+        error!("Could not load assets: {err}");
+        process::exit(ExitCode::LoadAssets as i32)
+    })
+}
+
+/// Returns the directory containing the assets shipped with the game: `res` next to the executable, or the one in
+/// the source tree during development.
+fn game_dir() -> PathBuf {
+    env::current_exe().ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join("res")))
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../res"))
+}
+
+fn load_recording(path: &Path) -> Recording {
+    Recording::load(path).unwrap_or_else(|err| {
+        error!("Could not load recording from {}: {}", path.display(), err);
+        process::exit(ExitCode::LoadRecording as i32)
     })
 }
 
+/// Returns where the ship of player `index` out of `count` starts: side by side, centered at the origin.
+#[allow(clippy::cast_precision_loss)]
+fn start_position(index: usize, count: usize) -> Vector3<f32> {
+    Vector3::new(2.0 * index as f32 - (count - 1) as f32, 0.0, 0.0)
+}
+
+/// Creates a camera at `position` looking at `look_at` with the projection given by `settings`.
+fn create_pose(settings: &Settings, position: Vector3<f32>, look_at: Vector3<f32>) -> CameraState {
+    #[allow(clippy::cast_precision_loss)]
+    let aspect_ratio = settings.window.width as f32 / settings.window.height as f32;
+    let mut camera = CameraState::new(position, look_at, aspect_ratio);
+    camera.set_projection(Deg(settings.graphics.fov), settings.graphics.near, settings.graphics.far);
+    camera
+}
+
+/// Renders a single frame without opening a window and saves it to `path`, after playing back `replay` if given.
+fn capture_headless(settings: &Settings, assets: &mut Assets, replay: Option<&Path>, path: &Path) -> ! {
+    debug!("create headless display");
+    let headless = display::create_headless(settings).unwrap_or_else(|err| {
+        error!("Could not create headless display: {err}");
+        process::exit(ExitCode::CreateDisplay as i32)
+    });
+    let mut scene = Scene::new(&headless, assets, settings);
+    // recordings cover the first player only
+    let mut pilot = Pilot::new(settings, None, &scene, scene.ships[0]);
+
+    if let Some(replay) = replay {
+        let mut player = Player::new(load_recording(replay));
+        #[allow(clippy::cast_precision_loss)]
+        let time = 1.0 / player.tick_rate() as f32;
+        while let Some(tick) = player.next_tick() {
+            pilot.switch_cameras(tick.next_camera, tick.look_behind, settings.camera.blend_time);
+            pilot.update(tick.axes, &mut scene, time);
+        }
+    }
+
+    let size = (settings.window.width, settings.window.height);
+    let view = pilot.view(Viewport::full(size), 1.0);
+    let result = Offscreen::new(&headless, size.0, size.1)
+            .and_then(|offscreen| offscreen.render(&headless, |target| scene.draw(target, &[view])))
+            .and_then(|image| capture::save_png(&image, path));
+    if let Err(err) = result {
+        error!("Could not save screenshot to {}: {}", path.display(), err);
+        process::exit(ExitCode::SaveScreenshot as i32)
+    }
+    info!("saved screenshot to {}", path.display());
+    process::exit(0)
+}
+
+fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{message}");
+        process::exit(ExitCode::InvalidArguments as i32)
+    });
+    if options.help {
+        println!("{}", options::USAGE);
+        process::exit(0);
+    }
+
+    // the settings are required to set up the logger, so errors are reported after that
+    let settings_path = settings::default_path(APPLICATION_NAME);
+    let settings = settings_path.as_deref().map_or_else(|| Ok(Settings::default()), Settings::load_or_default);
+
+    let log_settings = settings.as_ref().map_or_else(|_| LogSettings::default(), |settings| settings.log.clone());
+    let log = logging::init(&log_settings, logging::default_dir(APPLICATION_NAME).as_deref())
+            .unwrap_or_else(|err| {
+                eprintln!("Could not initialize logging: {err}");
+                process::exit(ExitCode::InitLogging as i32)
+            });
+    if let Some(path) = log.file_path() {
+        debug!("logging into {}", path.display());
+    }
+
+    debug!("load settings");
+    let settings = settings.unwrap_or_else(|err| {
+        error!("Could not load settings: {err}");
+        process::exit(ExitCode::LoadSettings as i32)
+    });
+    if let Some(path) = &settings_path {
+        save_template(path, "settings", |path| settings.save(path));
+    } else {
+        warn!("Could not determine the configuration directory; using default settings");
+    }
+
+    debug!("load key bindings");
+    let bindings_path = settings::config_dir(APPLICATION_NAME).map(|dir| dir.join(bindings::BINDINGS_FILE_NAME));
+    let bindings = bindings_path.as_deref().map_or_else(|| Ok(Bindings::default()), Bindings::load_or_default)
+            .unwrap_or_else(|err| {
+                error!("Could not load key bindings: {err}");
+                process::exit(ExitCode::LoadBindings as i32)
+            });
+    if let Some(path) = &bindings_path {
+        save_template(path, "key bindings", |path| bindings.save(path));
+    }
+
+    let game_dir = options.assets.clone().unwrap_or_else(game_dir);
+    let mut assets = Assets::new(Vfs::with_search_path(APPLICATION_NAME, &game_dir));
+    debug!("search assets in {}", assets.vfs().search_path().join(", "));
+
+    if let Some(path) = &options.screenshot {
+        capture_headless(&settings, &mut assets, options.replay.as_deref(), path);
+    }
+
+    debug!("create display");
+    let mut engine = Engine::new(&settings)
+            .unwrap_or_else(|err| {
+                error!("Could not create display: {err}");
+                process::exit(ExitCode::CreateDisplay as i32)
+            });
 
+    let replay = options.replay.map(|path| {
+        let recording = load_recording(&path);
+        if recording.tick_rate() != engine.tick_rate() {
+            info!("recording has been made with {} ticks per second; simulating at that rate", recording.tick_rate());
+            engine.set_time_step(recording.tick_rate(), timing::DEFAULT_MAX_FRAME_TIME);
+        }
+        info!("replaying {} ticks from {}", recording.len(), path.display());
+        Player::new(recording)
+    });
+
+    let display = engine.display();
+    log.set_header(&display::details(display));
+
+    let scene = Scene::new(display, &mut assets, &settings);
+
+    let recording = options.record.map(|path| (path, Recording::new(engine.tick_rate())));
+
+    let players = &settings.players;
+    let pilots = scene.ships.iter().enumerate().map(|(index, &ship)| {
+        let input = if players.count == 1 { None } else { Some(players.inputs[index]) };
+        Pilot::new(&settings, input, &scene, ship)
+    }).collect();
+
+    let game = Game {
+        display: display.clone(),
+        keyboard: keyboard::Control::new(bindings),
+        gamepad: gamepad::Control::new(settings.input.gamepad.clone()),
+        mouse: mouse::Control::new(settings.input.mouse.clone()),
+        recording,
+        replay,
+        pilots,
+        assets,
+        next_watch: options.watch.then(Instant::now),
+        scene,
+        screenshot_requested: false,
+        settings,
+        settings_path,
+        settings_changed: false,
+    };
+
+    debug!("start main loop …");
+    engine.run(game);
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "usage: reactor-ls-game [--record <file>] [--replay <file>] [--screenshot <file>] [--assets <dir>] [--watch]";
+
+/// Options given on the command line.
+#[derive(Default)]
+pub struct Options {
+    /// where to save the input of this session
+    pub record: Option<PathBuf>,
+    /// recorded input to play back instead of live input
+    pub replay: Option<PathBuf>,
+    /// render a single frame without opening a window and save it as PNG file
+    pub screenshot: Option<PathBuf>,
+    /// directory containing the assets shipped with the game
+    pub assets: Option<PathBuf>,
+    /// reload shaders and meshes whenever their files change
+    pub watch: bool,
+    /// print the usage instead of starting the game
+    pub help: bool,
+}
+
+impl Options {
+
+    /// Parses the command line arguments, excluding the program name.
+    pub fn parse<I>(mut args: I) -> Result<Self, String>
+    where
+        I: Iterator<Item = String>
+    {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => options.record = Some(args.next().ok_or_else(|| format!("missing file after --record\n{USAGE}"))?.into()),
+                "--replay" => options.replay = Some(args.next().ok_or_else(|| format!("missing file after --replay\n{USAGE}"))?.into()),
+                "--screenshot" => options.screenshot = Some(args.next().ok_or_else(|| format!("missing file after --screenshot\n{USAGE}"))?.into()),
+                "--assets" => options.assets = Some(args.next().ok_or_else(|| format!("missing directory after --assets\n{USAGE}"))?.into()),
+                "--watch" => options.watch = true,
+                "--help" | "-h" => options.help = true,
+                _ => return Err(format!("unknown argument `{arg}`\n{USAGE}")),
+            }
+        }
+        Ok(options)
+    }
+
+}